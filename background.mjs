@@ -1,3 +1,5 @@
+import { main } from "./wasm/background/pkg/ya_ya_background.js";
+
 console.log("hello from background script");
 
 const rtm = typeof browser !== "undefined" ? browser : chrome;
@@ -7,3 +9,5 @@ rtm.runtime.onInstalled.addListener(() => {
     console.log("onInstalled openOptionsPage");
   });
 });
+
+main();
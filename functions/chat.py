@@ -0,0 +1,134 @@
+import logging
+import tomlkit
+import os
+import json
+import base64
+import cors
+import db_conn
+
+
+from yandex_cloud_ml_sdk import AsyncYCloudML
+from langchain_core.messages import AIMessage, HumanMessage, SystemMessage
+
+
+with open('./prompts.toml', 'r', encoding='utf-8') as file:
+    file_str = file.read()
+
+prompts = tomlkit.parse(file_str)
+
+logging.getLogger().setLevel(logging.DEBUG)
+
+async def handler(event, context):
+    origin = cors.extension_origin(event)
+    if origin is None:
+        return cors.forbidden()
+
+    data = None
+    if event['isBase64Encoded']:
+        data = base64.b64decode(event['body'])
+    else:
+        data = event['body']
+
+    body = json.loads(data)
+
+    kind = body['kind']
+    content = body['content']
+    ctx = body['context']
+    rejected = body.get('rejected') or []
+    answer = body['answer']
+    turns = body.get('turns') or []
+    question = body['question']
+
+    iam_token = context.token['access_token']
+    sdk = AsyncYCloudML(folder_id=os.environ['FN_MODEL_FOLDER_ID'], auth=iam_token)
+    model = sdk.models.completions('yandexgpt-lite', model_version='rc')
+    model = model.configure(temperature=0.42).langchain(model_type="chat", timeout=context.get_remaining_time_in_millis())
+
+    langchain_result = await model.ainvoke([
+        SystemMessage(content=prompts['system'][kind]['goal']),
+        SystemMessage(content=prompts['system']['rules']),
+        SystemMessage(content=prompts['system']['chat']['interlude']),
+        HumanMessage(content=original_prompt(kind, content, ctx)),
+        *rejected_messages(rejected),
+        AIMessage(content=answer),
+        *turn_messages(turns),
+        HumanMessage(content=question),
+    ])
+
+    logging.info("{}".format(langchain_result.usage_metadata))
+
+    pool = await db_conn.conn_pool()
+
+    result_sets = await pool.execute_with_retries(
+        """
+        DECLARE $word AS Utf8?;
+        DECLARE $context AS Utf8?;
+        DECLARE $text AS Utf8?;
+        DECLARE $question AS Utf8;
+        DECLARE $annotation AS Utf8;
+        DECLARE $origin AS Utf8;
+
+        INSERT INTO records (word, context, text, question, annotation, origin)
+            VALUES($word, $context, $text, $question, $annotation, $origin) RETURNING id;
+        """,
+        {
+            "$word": content if kind == 'word' else None,
+            "$context": ctx if kind == 'word' else None,
+            "$text": content if kind == 'text' else None,
+            "$question": question,
+            "$annotation": langchain_result.content,
+            "$origin": origin,
+        }
+    )
+
+    id = result_sets[0].rows[0].id
+
+    await pool.stop()
+
+    return {
+        'statusCode': 200,
+        'headers': {
+            'Content-Type': 'application/json',
+            'Access-Control-Allow-Origin': origin
+        },
+        'body': {
+            'id': id,
+            'annotation': langchain_result.content,
+        }
+    }
+
+
+def original_prompt(kind, content, ctx):
+    if kind == 'word':
+        return "{prompt}\n\n{word_prompt} <СЛОВО>{word}</СЛОВО> \n\n {ctx_prompt} <КОНТЕКСТ>{ctx}</КОНТЕКСТ>".format(
+            prompt=prompts['user']['word']['prompt'],
+            word_prompt=prompts['user']['word']['word_prompt'],
+            ctx_prompt=prompts['user']['word']['ctx_prompt'],
+            word=content,
+            ctx=ctx,
+        )
+
+    return "{prompt} \n\n <ТЕКСТ> {text} </ТЕКСТ>".format(
+        prompt=prompts['user']['text']['prompt'],
+        text=content,
+    )
+
+def rejected_messages(rejected):
+    messages = []
+    for annotation in rejected:
+        messages += [
+            AIMessage(content=annotation),
+            HumanMessage(content=prompts['user']['reword']),
+        ]
+
+    return messages
+
+def turn_messages(turns):
+    messages = []
+    for turn in turns:
+        messages += [
+            HumanMessage(content=turn['question']),
+            AIMessage(content=turn['answer']),
+        ]
+
+    return messages
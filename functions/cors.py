@@ -0,0 +1,16 @@
+import os
+
+
+def extension_origin(event):
+    """The Origin header of the request when it is one of the extension's, None otherwise."""
+    allowed = os.environ['FN_EXTENSION_ORIGINS'].split()
+    origin = event['headers'].get('Origin', '')
+
+    return origin if origin in allowed else None
+
+
+def forbidden():
+    return {
+        'statusCode': 403,
+        'body': 'Forbidden',
+    }
@@ -6,12 +6,17 @@ import base64
 import os
 import ydb
 import ydb.iam
+import cors
 import db_conn
 
 
 logging.getLogger().setLevel(logging.DEBUG)
 
 async def handler(event, context):
+    origin = cors.extension_origin(event)
+    if origin is None:
+        return cors.forbidden()
+
     data = None
     if event['isBase64Encoded']:
         data = base64.b64decode(event['body'])
@@ -47,7 +52,7 @@ async def handler(event, context):
     return {
         'statusCode': 200,
         'headers': {
-            'Access-Control-Allow-Origin': '*'
+            'Access-Control-Allow-Origin': origin
         },
         'body': True,
     }
@@ -3,6 +3,7 @@ import tomlkit
 import os
 import json
 import base64
+import cors
 import db_conn
 
 
@@ -18,6 +19,10 @@ prompts = tomlkit.parse(file_str)
 logging.getLogger().setLevel(logging.DEBUG)
 
 async def handler(event, context):
+    origin = cors.extension_origin(event)
+    if origin is None:
+        return cors.forbidden()
+
     data = None
     if event['isBase64Encoded']:
         data = base64.b64decode(event['body'])
@@ -27,8 +32,9 @@ async def handler(event, context):
     body = json.loads(data)
 
     text = body['text']
-    origin = body['origin']
-    annotation_prev = body['previous']
+    annotations_rejected = body.get('rejected') or []
+    if not annotations_rejected and body.get('previous'):
+        annotations_rejected = [body['previous']]
 
     prompt = prompts['user']['text']['prompt']
 
@@ -48,7 +54,7 @@ async def handler(event, context):
         SystemMessage(content=prompts['system']['text']['interlude']),
         # *example_prompts(),
         HumanMessage(content=translate_prompt),
-        *maybe_previous(annotation_prev)
+        *rejected_messages(annotations_rejected)
     ])
 
     logging.info("{}".format(langchain_result.usage_metadata))
@@ -88,14 +94,15 @@ async def handler(event, context):
     }
 
 
-def maybe_previous(previous):
-    if previous:
-        return [
-            AIMessage(content=previous),
+def rejected_messages(rejected):
+    messages = []
+    for annotation in rejected:
+        messages += [
+            AIMessage(content=annotation),
             HumanMessage(content=prompts['user']['reword']),
         ]
 
-    return []
+    return messages
 
 def example_prompts():
     prompt = prompts['user']['text']['prompt']
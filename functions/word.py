@@ -3,6 +3,7 @@ import tomlkit
 import os
 import json
 import base64
+import cors
 import db_conn
 
 
@@ -18,6 +19,10 @@ prompts = tomlkit.parse(file_str)
 logging.getLogger().setLevel(logging.DEBUG)
 
 async def handler(event, context):
+    origin = cors.extension_origin(event)
+    if origin is None:
+        return cors.forbidden()
+
     data = None
     if event['isBase64Encoded']:
         data = base64.b64decode(event['body'])
@@ -25,11 +30,12 @@ async def handler(event, context):
         data = event['body']
 
     body = json.loads(data)
-    origin = event['headers']['Origin']
 
     word = body['word']
     ctx = body['context']
-    annotation_prev = body['previous']
+    annotations_rejected = body.get('rejected') or []
+    if not annotations_rejected and body.get('previous'):
+        annotations_rejected = [body['previous']]
 
     ctx_prompt = prompts['user']['word']['ctx_prompt']
     word_prompt = prompts['user']['word']['word_prompt']
@@ -55,7 +61,7 @@ async def handler(event, context):
         *example_prompts(),
         HumanMessage(content=prompts['user']['word']['prompt']),
         HumanMessage(content=translate_prompt),
-        *maybe_previous(annotation_prev)
+        *rejected_messages(annotations_rejected)
     ])
 
     logging.info("{}".format(langchain_result.usage_metadata))
@@ -97,14 +103,15 @@ async def handler(event, context):
     }
 
 
-def maybe_previous(previous):
-    if previous:
-        return [
-            AIMessage(content=previous),
+def rejected_messages(rejected):
+    messages = []
+    for annotation in rejected:
+        messages += [
+            AIMessage(content=annotation),
             HumanMessage(content=prompts['user']['reword']),
         ]
 
-    return []
+    return messages
 
 def example_prompts():
     ctx_prompt = prompts['user']['word']['ctx_prompt']
@@ -0,0 +1,4 @@
+import "../wasm/panel/pkg/ya_ya_panel.js";
+import "../style.css";
+
+console.debug("loaded side panel scripts and styles");
@@ -0,0 +1,161 @@
+use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
+
+use common::{
+    annotation::{Annotation, AnnotationRequest},
+    client,
+    error::YaYaError,
+    message::{Annotate, Message, Reply, Request},
+};
+use futures::future::{FutureExt, LocalBoxFuture, Shared};
+
+use super::{cache::Cache, limiter::Limiter, retry::Retry, timer};
+
+/// Annotations kept for the pages that ask for them again.
+const CACHE_CAPACITY: usize = 256;
+/// Requests sent at once before the rest wait for [`PER_SECOND`].
+const BURST: u32 = 5;
+const PER_SECOND: f64 = 2.0;
+const RETRY: Retry = Retry {
+    attempts: 3,
+    delay: 500.0,
+    max_delay: 4000.0,
+};
+
+/// A request and the explanations the reader did not understand.
+type Key = (AnnotationRequest, Vec<String>);
+type Pending = Shared<LocalBoxFuture<'static, Result<Annotation, YaYaError>>>;
+
+/// Sends the requests of every tab and page to the endpoints: caches the annotations,
+/// joins the same requests in flight, spaces the requests out and retries the failed ones.
+pub struct Broker {
+    cache: RefCell<Cache<Key, Annotation>>,
+    in_flight: RefCell<HashMap<Key, Pending>>,
+    limiter: RefCell<Limiter>,
+}
+
+impl Broker {
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self {
+            cache: RefCell::new(Cache::new(CACHE_CAPACITY)),
+            in_flight: RefCell::new(HashMap::new()),
+            limiter: RefCell::new(Limiter::new(BURST, PER_SECOND)),
+        })
+    }
+
+    /// The reply to `request`, `None` for the requests to content scripts and the side panel.
+    pub fn answer(self: &Rc<Self>, request: Request) -> Option<LocalBoxFuture<'static, Reply>> {
+        let broker = self.clone();
+
+        match request {
+            Request::Annotate(message) => Some(
+                async move {
+                    let annotation = broker.annotate(&message).await;
+                    message.reply(annotation)
+                }
+                .boxed_local(),
+            ),
+            Request::Ask(message) => Some(
+                async move {
+                    let answer = broker
+                        .with_retries(|| {
+                            client::ask(
+                                &message.request,
+                                &message.rejected,
+                                &message.answer,
+                                &message.turns,
+                                &message.question,
+                            )
+                        })
+                        .await;
+                    message.reply(answer)
+                }
+                .boxed_local(),
+            ),
+            Request::RecordSuccess(message) => Some(
+                async move {
+                    let recorded = broker
+                        .with_retries(|| client::success_record(message.id, message.result))
+                        .await;
+                    message.reply(recorded)
+                }
+                .boxed_local(),
+            ),
+            // Acknowledged here, so pages do not fail to send it while no side panel is open.
+            Request::ExplanationsChanged(message) => {
+                Some(async move { message.reply(()) }.boxed_local())
+            }
+            Request::GetSelection(_)
+            | Request::ScanDifficultWords(_)
+            | Request::OpenReader(_)
+            | Request::Explain(_)
+            | Request::GetExplanations(_)
+            | Request::Reveal(_) => None,
+        }
+    }
+
+    async fn annotate(self: &Rc<Self>, message: &Annotate) -> Result<Annotation, YaYaError> {
+        let key = (message.request.clone(), message.rejected.clone());
+        if let Some(annotation) = self.cache.borrow_mut().get(&key) {
+            log::debug!("broker.rs :: Cached {:?}", message.request.content);
+            return Ok(annotation);
+        }
+
+        let in_flight = self.in_flight.borrow().get(&key).cloned();
+        let pending = match in_flight {
+            Some(pending) => {
+                log::debug!("broker.rs :: Joining {:?}", message.request.content);
+                pending
+            }
+            None => {
+                let broker = self.clone();
+                let message = message.clone();
+                let pending = async move {
+                    broker
+                        .with_retries(|| client::annotate(&message.request, &message.rejected))
+                        .await
+                }
+                .boxed_local()
+                .shared();
+                self.in_flight
+                    .borrow_mut()
+                    .insert(key.clone(), pending.clone());
+                pending
+            }
+        };
+
+        let annotation = pending.await;
+        self.in_flight.borrow_mut().remove(&key);
+        if let Ok(annotation) = &annotation {
+            self.cache.borrow_mut().insert(key, annotation.clone());
+        }
+
+        annotation
+    }
+
+    /// Calls the endpoint in turn with the other requests, again while it fails with [`RETRY`].
+    async fn with_retries<T, F>(&self, call: impl Fn() -> F) -> Result<T, YaYaError>
+    where
+        F: Future<Output = Result<T, YaYaError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let wait = self.limiter.borrow_mut().reserve(timer::now());
+            if wait > 0.0 {
+                log::debug!("broker.rs :: Waiting {wait}ms for the rate limit");
+                timer::sleep(wait).await;
+            }
+
+            match call().await {
+                Ok(value) => return Ok(value),
+                Err(err) => match RETRY.after(attempt, err) {
+                    Some(delay) => {
+                        log::warn!("broker.rs :: Retrying in {delay}ms after {err:?}");
+                        attempt += 1;
+                        timer::sleep(delay).await;
+                    }
+                    None => return Err(err),
+                },
+            }
+        }
+    }
+}
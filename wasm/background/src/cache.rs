@@ -0,0 +1,75 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// Least recently used entries are dropped past the capacity.
+#[derive(Debug)]
+pub struct Cache<K, V> {
+    capacity: usize,
+    entries: HashMap<K, (u64, V)>,
+    /// Bumped on every use, the entry with the lowest one is the least recently used.
+    clock: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Cache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.clock += 1;
+        let (used, value) = self.entries.get_mut(key)?;
+        *used = self.clock;
+
+        Some(value.clone())
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.clock += 1;
+        self.entries.insert(key, (self.clock, value));
+
+        if self.entries.len() > self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_recently_used() {
+        let mut cache = Cache::new(2);
+        cache.insert("синхротрон", 1);
+        cache.insert("коллайдер", 2);
+        assert_eq!(cache.get(&"синхротрон"), Some(1));
+
+        cache.insert("адрон", 3);
+
+        assert_eq!(cache.get(&"коллайдер"), None);
+        assert_eq!(cache.get(&"синхротрон"), Some(1));
+        assert_eq!(cache.get(&"адрон"), Some(3));
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let mut cache = Cache::new(2);
+        cache.insert("синхротрон", 1);
+        cache.insert("синхротрон", 2);
+
+        cache.insert("коллайдер", 3);
+
+        assert_eq!(cache.get(&"синхротрон"), Some(2));
+        assert_eq!(cache.get(&"коллайдер"), Some(3));
+    }
+}
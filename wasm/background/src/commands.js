@@ -0,0 +1,8 @@
+const rtm = typeof browser !== "undefined" ? browser : chrome;
+
+// Older browsers leave out the tab, the active one is meant then.
+export function onCommand(callback) {
+  rtm.commands.onCommand.addListener((command, tab) => {
+    callback(command, tab?.id);
+  });
+}
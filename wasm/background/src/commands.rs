@@ -0,0 +1,23 @@
+use common::message::ExplainTarget;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "/src/commands.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "onCommand")]
+    fn on_command(callback: &JsValue);
+}
+
+/// Explains the target of the keyboard commands of `manifest.json`, the reader rebinds them
+/// in the shortcuts settings of the browser.
+pub fn listen() {
+    let callback =
+        Closure::<dyn Fn(String, Option<i32>)>::new(|command: String, tab_id: Option<i32>| {
+            match ExplainTarget::from_name(&command) {
+                Some(target) => crate::explain_in_tab(target, tab_id),
+                None => log::warn!("commands.rs :: Ignoring the command {command}"),
+            }
+        });
+
+    // Listens for as long as the worker lives.
+    on_command(&callback.into_js_value());
+}
@@ -0,0 +1,60 @@
+mod broker;
+mod cache;
+mod commands;
+mod limiter;
+mod menus;
+mod retry;
+mod timer;
+
+use cfg_if::cfg_if;
+use common::{
+    message::{listen_async, send_to_active_tab, send_to_tab, Explain, ExplainTarget},
+    settings::{load_stored, migrate_on_change},
+};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use broker::Broker;
+
+#[wasm_bindgen]
+pub fn main() {
+    _ = console_log::init_with_level({
+        cfg_if! {if #[cfg(debug_assertions)] {
+            log::Level::Debug
+        }else {
+            log::Level::Info
+        }}
+    });
+    console_error_panic_hook::set_once();
+    log::info!("init log background");
+
+    let broker = Broker::new();
+    if let Err(err) = listen_async(move |request, _| broker.answer(request)) {
+        log::error!("lib.rs :: Failed to listen to messages: {err:?}");
+    }
+
+    menus::listen();
+    spawn_local(menus::register());
+    commands::listen();
+
+    // Outdated settings are migrated here rather than by every page, at startup
+    // and whenever they change.
+    spawn_local(async {
+        let settings = load_stored().await;
+        log::debug!("lib.rs :: Settings version {}", settings.version);
+    });
+    migrate_on_change();
+}
+
+/// Asks the content script of the tab, or of the active one, to explain `target` in place.
+fn explain_in_tab(target: ExplainTarget, tab_id: Option<i32>) {
+    spawn_local(async move {
+        let explained = match tab_id {
+            Some(tab_id) => send_to_tab(tab_id, Explain { target }).await,
+            None => send_to_active_tab(Explain { target }).await,
+        };
+        if let Err(err) = explained {
+            log::error!("lib.rs :: Failed to explain {target:?} in tab {tab_id:?}: {err:?}");
+        }
+    });
+}
@@ -0,0 +1,63 @@
+/// Token bucket: bursts of up to `capacity` requests, then `per_second` of them.
+///
+/// Requests over the limit are not refused, they wait their turn.
+#[derive(Debug)]
+pub struct Limiter {
+    capacity: f64,
+    per_second: f64,
+    /// Negative when requests are waiting.
+    tokens: f64,
+    /// Milliseconds of the last reservation.
+    updated: Option<f64>,
+}
+
+impl Limiter {
+    pub fn new(capacity: u32, per_second: f64) -> Self {
+        Self {
+            capacity: capacity.into(),
+            per_second,
+            tokens: capacity.into(),
+            updated: None,
+        }
+    }
+
+    /// Takes a token at `now`, returns the milliseconds to wait before sending.
+    pub fn reserve(&mut self, now: f64) -> f64 {
+        let elapsed = self.updated.map_or(0.0, |updated| (now - updated).max(0.0));
+        self.tokens = (self.tokens + elapsed / 1000.0 * self.per_second).min(self.capacity) - 1.0;
+        self.updated = Some(now);
+
+        if self.tokens >= 0.0 {
+            0.0
+        } else {
+            -self.tokens / self.per_second * 1000.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_then_queues() {
+        let mut limiter = Limiter::new(2, 2.0);
+
+        assert_eq!(limiter.reserve(0.0), 0.0);
+        assert_eq!(limiter.reserve(0.0), 0.0);
+        assert_eq!(limiter.reserve(0.0), 500.0);
+        assert_eq!(limiter.reserve(0.0), 1000.0);
+    }
+
+    #[test]
+    fn refills_up_to_capacity() {
+        let mut limiter = Limiter::new(2, 2.0);
+        limiter.reserve(0.0);
+        limiter.reserve(0.0);
+
+        assert_eq!(limiter.reserve(500.0), 0.0);
+        assert_eq!(limiter.reserve(60_000.0), 0.0);
+        assert_eq!(limiter.reserve(60_000.0), 0.0);
+        assert_eq!(limiter.reserve(60_000.0), 500.0);
+    }
+}
@@ -0,0 +1,15 @@
+const rtm = typeof browser !== "undefined" ? browser : chrome;
+
+// Menus outlive the worker, they are made anew on every start to stay in sync.
+export async function createMenus(items) {
+  await rtm.contextMenus.removeAll();
+  for (const item of JSON.parse(items)) {
+    rtm.contextMenus.create(item);
+  }
+}
+
+export function onMenuClicked(callback) {
+  rtm.contextMenus.onClicked.addListener((info, tab) => {
+    callback(String(info.menuItemId), tab?.id);
+  });
+}
@@ -0,0 +1,61 @@
+use common::message::ExplainTarget;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "/src/menus.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "createMenus", catch)]
+    async fn create_menus(items: &str) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(js_name = "onMenuClicked")]
+    fn on_menu_clicked(callback: &JsValue);
+}
+
+/// A `contextMenus.create` item.
+#[derive(serde::Serialize)]
+struct MenuItem {
+    id: &'static str,
+    title: &'static str,
+    contexts: &'static [&'static str],
+}
+
+fn item(target: ExplainTarget) -> MenuItem {
+    let (title, contexts): (_, &[_]) = match target {
+        ExplainTarget::Selection => ("Объяснить на Ясном Языке", &["selection"]),
+        ExplainTarget::Word => ("Объяснить это слово на Ясном Языке", &["page"]),
+        ExplainTarget::Link => ("Объяснить текст ссылки на Ясном Языке", &["link"]),
+        ExplainTarget::Image => ("Объяснить описание картинки на Ясном Языке", &["image"]),
+        ExplainTarget::Paragraph => ("Упростить абзац или вернуть исходный", &["page"]),
+    };
+
+    MenuItem {
+        id: target.name(),
+        title,
+        contexts,
+    }
+}
+
+/// Adds the items of every [`ExplainTarget`] to the context menu of the pages.
+pub async fn register() {
+    let items = ExplainTarget::ALL.map(item);
+    let result = match serde_json::to_string(&items) {
+        Ok(items) => create_menus(&items).await.map(|_| ()),
+        Err(err) => Err(err.to_string().into()),
+    };
+
+    if let Err(err) = result {
+        log::error!("menus.rs :: Failed to create the context menu: {err:?}");
+    }
+}
+
+/// Sends the clicked item to the content script of its tab, which explains it in place.
+pub fn listen() {
+    let callback =
+        Closure::<dyn Fn(String, Option<i32>)>::new(|id: String, tab_id: Option<i32>| {
+            match ExplainTarget::from_name(&id) {
+                Some(target) => crate::explain_in_tab(target, tab_id),
+                None => log::warn!("menus.rs :: Ignoring the click on {id}"),
+            }
+        });
+
+    // Listens for as long as the worker lives.
+    on_menu_clicked(&callback.into_js_value());
+}
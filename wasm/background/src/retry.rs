@@ -0,0 +1,48 @@
+use common::error::YaYaError;
+
+/// Exponential backoff for failures that may pass.
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    /// Tries in total, the first one included.
+    pub attempts: u32,
+    /// Milliseconds before the first retry, doubled for every next one.
+    pub delay: f64,
+    pub max_delay: f64,
+}
+
+impl Retry {
+    /// Milliseconds to wait after the failed `attempt`, counted from 0, `None` to give up.
+    pub fn after(&self, attempt: u32, err: YaYaError) -> Option<f64> {
+        let retryable = matches!(err, YaYaError::ServerError | YaYaError::UnknownError);
+        if !retryable || attempt + 1 >= self.attempts {
+            return None;
+        }
+
+        Some((self.delay * 2f64.powi(attempt as i32)).min(self.max_delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RETRY: Retry = Retry {
+        attempts: 4,
+        delay: 500.0,
+        max_delay: 1500.0,
+    };
+
+    #[test]
+    fn backs_off() {
+        assert_eq!(RETRY.after(0, YaYaError::ServerError), Some(500.0));
+        assert_eq!(RETRY.after(1, YaYaError::UnknownError), Some(1000.0));
+        assert_eq!(RETRY.after(2, YaYaError::ServerError), Some(1500.0));
+        assert_eq!(RETRY.after(3, YaYaError::ServerError), None);
+    }
+
+    #[test]
+    fn gives_up_on_errors_that_stay() {
+        assert_eq!(RETRY.after(0, YaYaError::ClientError), None);
+        assert_eq!(RETRY.after(0, YaYaError::IntegrationError), None);
+    }
+}
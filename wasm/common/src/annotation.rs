@@ -1,4 +1,8 @@
-use super::error::YaYaError;
+use super::{
+    error::YaYaError,
+    message::{self, Annotate, Ask, RecordSuccess},
+    sentences::split_sentences,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 pub struct Annotation {
@@ -6,87 +10,124 @@ pub struct Annotation {
     pub id: usize,
 }
 
-pub async fn annotate_word(
-    word: String,
-    context: String,
-    previous: Option<String>,
-) -> Result<Annotation, YaYaError> {
-    let client = reqwest::Client::new();
-
-    let body = json::object! {
-        word: word,
-        context: context,
-        previous: previous
-    };
-
-    let res = client
-        .post(
-            format!(
-                "{}/translate-word",
-                crate::env::EXTENSION_PUBLIC_TRANSLATE_URL
-            )
-            .as_str(),
-        )
-        .body(json::stringify(body))
-        .send()
-        .await?;
-
-    // TODO: why doesnt it work with `res.json()`... ?
-    let text = res.text().await?;
-
-    Ok(serde_json::from_str(text.as_str())?)
+/// Marker of the contextual meaning in word annotations, see `functions/prompts.toml`.
+const CONTEXT_MEANING_MARKER: &str = "в контексте";
+
+/// Longest gloss, in chars, before it is cut with an ellipsis.
+const MAX_GLOSS_LENGTH: usize = 120;
+
+impl Annotation {
+    /// A one-line gloss: the first sentence of the contextual meaning,
+    /// or of the first paragraph when the answer does not follow the template.
+    pub fn gloss(&self) -> String {
+        let lines = self
+            .annotation
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("```"))
+            .collect::<Vec<_>>();
+
+        let in_context = lines.iter().enumerate().find_map(|(at, line)| {
+            let rest = line
+                .strip_prefix("**")
+                .and_then(|l| l.split_once("**"))
+                .filter(|(label, _)| label.to_lowercase().contains(CONTEXT_MEANING_MARKER))
+                .map(|(_, rest)| rest.trim())?;
+
+            if rest.is_empty() {
+                lines.get(at + 1).copied()
+            } else {
+                Some(rest)
+            }
+        });
+
+        let paragraph = in_context.or(lines.first().copied()).unwrap_or_default();
+        let plain = paragraph
+            .chars()
+            .filter(|c| !matches!(c, '*' | '_' | '`'))
+            .collect::<String>();
+        let plain = plain.trim_start_matches(['-', '—', '–', ':', ' ']).trim();
+
+        let sentence = split_sentences(plain).first().copied().unwrap_or(plain);
+
+        if sentence.chars().count() > MAX_GLOSS_LENGTH {
+            let cut = sentence
+                .chars()
+                .take(MAX_GLOSS_LENGTH - 1)
+                .collect::<String>();
+            format!("{}…", cut.trim_end())
+        } else {
+            sentence.to_string()
+        }
+    }
+}
+
+/// Which endpoint explains the content.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum RequestKind {
+    Word,
+    Text,
 }
 
-pub async fn annotate_text(
-    text: String,
-    origin: String,
-    previous: Option<String>,
-) -> Result<Annotation, YaYaError> {
-    let client = reqwest::Client::new();
-
-    let body = json::object! {
-        text: text,
-        origin: origin,
-        previous: previous
-    };
-
-    let res = client
-        .post(
-            format!(
-                "{}/translate-text",
-                crate::env::EXTENSION_PUBLIC_TRANSLATE_URL
-            )
-            .as_str(),
-        )
-        .body(json::stringify(body))
-        .send()
-        .await?;
-
-    // TODO: why doesnt it work with `res.json()`... ?
-    let text = res.text().await?;
-
-    Ok(serde_json::from_str(text.as_str())?)
+impl RequestKind {
+    /// Name of the kind in the chat request, see `functions/chat.py`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Word => "word",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// A follow-up question and the answer to it.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub question: String,
+    pub answer: Annotation,
+}
+
+/// Everything needed to ask for an annotation of a trigger.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnnotationRequest {
+    pub kind: RequestKind,
+    pub content: String,
+    /// Surrounding words for [`RequestKind::Word`], page origin for [`RequestKind::Text`].
+    pub context: String,
+}
+
+impl AnnotationRequest {
+    /// Asks the background for an explanation, different from the `rejected` ones, oldest first.
+    pub async fn send(self, rejected: Vec<String>) -> Result<Annotation, YaYaError> {
+        message::send(Annotate {
+            request: self,
+            rejected,
+        })
+        .await?
+    }
+
+    /// Asks a follow-up `question` about the `answer` the reader has seen, after the earlier `turns`.
+    ///
+    /// `rejected` are the versions `answer` replaced, oldest first.
+    pub async fn ask(
+        self,
+        rejected: Vec<String>,
+        answer: String,
+        turns: Vec<Turn>,
+        question: String,
+    ) -> Result<Annotation, YaYaError> {
+        message::send(Ask {
+            request: self,
+            rejected,
+            answer,
+            turns,
+            question,
+        })
+        .await?
+    }
 }
 
+/// Tells whether the annotation with `id` was clear.
 pub async fn success_record(id: usize, result: bool) -> Result<(), YaYaError> {
-    let client = reqwest::Client::new();
-
-    let body = json::object! {
-        id: id,
-        result: result
-    };
-
-    client
-        .post(
-            format!(
-                "{}/success-record",
-                crate::env::EXTENSION_PUBLIC_TRANSLATE_URL
-            )
-            .as_str(),
-        )
-        .body(json::stringify(body))
-        .send()
-        .await?;
-
-    Ok(())
+    message::send(RecordSuccess { id, result }).await?
 }
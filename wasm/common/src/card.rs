@@ -0,0 +1,270 @@
+use leptos::*;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use super::{
+    annotation::{Annotation, AnnotationRequest},
+    conversation::Conversation,
+    error::*,
+    loading::Loading,
+    sanitize::{render_annotation, SENTENCE_ATTRIBUTE},
+    speech::{use_speech, Speech},
+};
+
+/// Class of the sentence being read aloud.
+const READING_CLASS: &str = "ya-ya-reading";
+
+/// The sentence spans of a rendered annotation, in reading order.
+fn sentence_spans(el: &Element) -> Vec<Element> {
+    let Ok(spans) = el.query_selector_all(&format!("[{SENTENCE_ATTRIBUTE}]")) else {
+        return vec![];
+    };
+
+    (0..spans.length())
+        .filter_map(|n| spans.get(n))
+        .filter_map(|node| node.dyn_into::<Element>().ok())
+        .collect()
+}
+
+/// Text of every sentence of a rendered annotation with its index, see [`render_annotation`].
+fn sentences_of(el: &Element) -> Vec<(String, String)> {
+    let mut sentences = Vec::<(String, String)>::new();
+
+    for span in sentence_spans(el) {
+        let index = span.get_attribute(SENTENCE_ATTRIBUTE).unwrap_or_default();
+        let text = span.text_content().unwrap_or_default();
+        match sentences.last_mut() {
+            Some((last, sentence)) if *last == index => sentence.push_str(&text),
+            _ => sentences.push((index, text)),
+        }
+    }
+
+    sentences
+}
+
+/// Annotation body with the watermark and the feedback footer.
+///
+/// Shows [`Loading`] while `annotation` is `None` and [`ErrorView`] on errors.
+/// The buttons are disabled while `disabled` is set and after a click, until the next annotation arrives.
+/// With `version` set to `(shown, total)` and more than one version, shows ‹ › to switch between them.
+/// With `follow_up` set, the reader can ask follow-up questions about the shown annotation.
+/// 🔊 reads the heading and the annotation aloud, highlighting the sentence being read.
+#[component]
+pub fn AnnotationCard(
+    #[prop(into)] annotation: Signal<Option<Result<Annotation, YaYaError>>>,
+    /// `true` for "Ясно", `false` for "Не понятно".
+    #[prop(into)]
+    on_feedback: Callback<bool>,
+    #[prop(into)] on_retry: Callback<()>,
+    /// Shown above the loading indicator.
+    #[prop(optional, into)]
+    heading: Option<MaybeSignal<String>>,
+    #[prop(optional, into)] disabled: MaybeSignal<bool>,
+    /// Index of the shown version and the number of versions.
+    #[prop(optional, into)]
+    version: Option<Signal<(usize, usize)>>,
+    /// Called with the index of the version to show.
+    #[prop(optional, into)]
+    on_version: Option<Callback<usize>>,
+    /// What the annotation explains, for follow-up questions.
+    #[prop(optional, into)]
+    follow_up: Option<MaybeSignal<AnnotationRequest>>,
+    /// Earlier versions the annotation replaced, sent along with follow-up questions.
+    #[prop(optional, into)]
+    rejected: MaybeSignal<Vec<String>>,
+    /// Reads the annotation aloud as soon as it arrives.
+    #[prop(optional, into)]
+    auto_read: MaybeSignal<bool>,
+) -> impl IntoView {
+    let (clicked, set_clicked) = create_signal(false);
+    let follow_up = store_value(follow_up);
+    let rejected = store_value(rejected);
+
+    create_render_effect(move |_| {
+        annotation.track();
+        set_clicked.set(false);
+    });
+
+    let disabled_btns = Signal::derive(move || disabled.get() || clicked.get());
+
+    let feedback = move |good: bool| {
+        set_clicked.set(true);
+        on_feedback.call(good);
+    };
+
+    let switch = move |shown: usize| {
+        if let Some(on_version) = on_version {
+            on_version.call(shown);
+        }
+    };
+
+    let navigation = move || {
+        let (shown, total) = version?.get();
+        (total > 1).then(|| {
+            view! {
+                <div class="ya-ya-versions">
+                    <button
+                        class="ya-ya-version-button"
+                        title="Предыдущий вариант"
+                        aria-label="Предыдущий вариант"
+                        disabled=shown == 0
+                        on:click=move |_| switch(shown - 1)
+                    >
+                        "‹"
+                    </button>
+                    <span aria-live="polite">{format!("{} / {}", shown + 1, total)}</span>
+                    <button
+                        class="ya-ya-version-button"
+                        title="Следующий вариант"
+                        aria-label="Следующий вариант"
+                        disabled=shown + 1 >= total
+                        on:click=move |_| switch(shown + 1)
+                    >
+                        "›"
+                    </button>
+                </div>
+            }
+        })
+    };
+
+    let content = create_memo(move |_| {
+        annotation
+            .get()
+            .map(|res| res.map(|a| (render_annotation(&a.annotation), a)))
+    });
+
+    let text_el = create_node_ref::<html::Pre>();
+    let speech = use_speech();
+    // Sentence index of every part handed to `speech`, `None` for the heading.
+    let spoken = store_value(Vec::<Option<String>>::new());
+    let headword = {
+        let heading = heading.clone();
+        Signal::derive(move || heading.as_ref().map(|h| h.get()))
+    };
+
+    let read = move || {
+        let Some(el) = text_el.get_untracked() else {
+            return;
+        };
+        let sentences = sentences_of(&el);
+
+        // Annotations of words usually start with the word itself.
+        let first = sentences.first().map(|(_, s)| s.trim().to_lowercase());
+        let heading = headword
+            .get_untracked()
+            .filter(|word| Some(word.trim().to_lowercase()) != first);
+
+        let (order, parts): (Vec<_>, Vec<_>) = heading
+            .map(|word| (None, word))
+            .into_iter()
+            .chain(sentences.into_iter().map(|(index, s)| (Some(index), s)))
+            .unzip();
+
+        spoken.set_value(order);
+        speech.speak(parts);
+    };
+
+    let is_reading = Signal::derive(move || speech.reading().get().is_some());
+
+    create_effect(move |_| {
+        let index = speech
+            .reading()
+            .get()
+            .and_then(|at| spoken.with_value(|order| order.get(at).cloned().flatten()));
+        let Some(el) = text_el.get() else {
+            return;
+        };
+
+        for span in sentence_spans(&el) {
+            let is_read = index.is_some() && span.get_attribute(SENTENCE_ATTRIBUTE) == index;
+            _ = span.class_list().toggle_with_force(READING_CLASS, is_read);
+        }
+    });
+
+    // A new annotation stops the reading of the old one.
+    create_effect(move |_| {
+        let is_ready = matches!(content.get(), Some(Ok(_)));
+        speech.stop();
+
+        if is_ready && auto_read.get_untracked() && Speech::is_supported() {
+            // The annotation is in the DOM on the next frame.
+            request_animation_frame(read);
+        }
+    });
+
+    let read_button = move || {
+        Speech::is_supported().then(|| {
+            view! {
+                <button
+                    class="ya-ya-read-button"
+                    title=move || if is_reading.get() { "Остановить чтение" } else { "Прочитать вслух" }
+                    aria-label=move || if is_reading.get() { "Остановить чтение" } else { "Прочитать вслух" }
+                    aria-pressed=move || is_reading.get().to_string()
+                    on:click=move |_| if is_reading.get_untracked() { speech.stop() } else { read() }
+                >
+                    "🔊"
+                </button>
+            }
+        })
+    };
+
+    // Announced to screen readers, errors are announced by `ErrorView`.
+    let status = move || match annotation.get() {
+        None => "Пояснение создаётся…",
+        Some(Ok(_)) => "Пояснение готово.",
+        Some(Err(_)) => "",
+    };
+
+    view! {
+        <p class="ya-ya-visually-hidden" role="status">{status}</p>
+        <Show
+            when=move || content.get().is_some()
+            fallback=move || view! {
+                <pre class="ya-ya-pre">
+                    {heading.clone().map(|heading| view! { <h3>{heading}</h3> })}
+                    <Loading/>
+                </pre>
+            }
+        >
+            <ErrorBoundary fallback=move |errors| view! {
+                <ErrorView errors=errors on_retry=move |_| on_retry.call(())/>
+            }>
+                {move || {
+                    let (content, answer) = content.get().ok_or(YaYaError::IntegrationError)??;
+                    let conversation = follow_up.get_value().map(|request| view! {
+                        <Conversation request answer rejected=rejected.get_value()/>
+                    });
+                    Result::<View, YaYaError>::Ok(view! {
+                        <div class="ya-ya-card-tools">
+                            {read_button}
+                            {navigation}
+                        </div>
+                        <pre class="ya-ya-pre" inner_html=content node_ref=text_el/>
+                        {conversation}
+                        <div class="ya-ya-water-mark">
+                            <hr/>
+                            <p>Ответ создан языковой моделью и может содержать ошибки.</p>
+                            <hr/>
+                        </div>
+                        <div class="ya-ya-footer">
+                            <button
+                                class="ya-ya-button"
+                                on:click=move |_| feedback(false)
+                                disabled=disabled_btns
+                            >
+                                "↺ Не понятно"
+                            </button>
+                            <button
+                                class="ya-ya-button-cta"
+                                on:click=move |_| feedback(true)
+                                disabled=disabled_btns
+                            >
+                                "✔︎ Ясно"
+                            </button>
+                        </div>
+                    }.into_view())
+                }}
+            </ErrorBoundary>
+        </Show>
+    }
+}
@@ -0,0 +1,93 @@
+//! HTTP client of the annotation endpoints, used by the background worker only,
+//! the pages go through [`crate::message`].
+
+use super::{
+    annotation::{Annotation, AnnotationRequest, RequestKind, Turn},
+    error::YaYaError,
+};
+
+fn endpoint(path: &str) -> String {
+    format!("{}/{path}", crate::env::EXTENSION_PUBLIC_TRANSLATE_URL)
+}
+
+async fn post(path: &str, body: json::JsonValue) -> Result<String, YaYaError> {
+    let res = reqwest::Client::new()
+        .post(endpoint(path).as_str())
+        .body(json::stringify(body))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // TODO: why doesnt it work with `res.json()`... ?
+    Ok(res.text().await?)
+}
+
+/// Asks for an explanation of the request, different from the `rejected` ones, oldest first.
+pub async fn annotate(
+    request: &AnnotationRequest,
+    rejected: &[String],
+) -> Result<Annotation, YaYaError> {
+    let text = match request.kind {
+        RequestKind::Word => {
+            let body = json::object! {
+                word: request.content.as_str(),
+                context: request.context.as_str(),
+                rejected: rejected.to_vec()
+            };
+            post("translate-word", body).await?
+        }
+        RequestKind::Text => {
+            let body = json::object! {
+                text: request.content.as_str(),
+                rejected: rejected.to_vec()
+            };
+            post("translate-text", body).await?
+        }
+    };
+
+    Ok(serde_json::from_str(text.as_str())?)
+}
+
+/// Asks a follow-up `question` about the `answer` the reader has seen, after the earlier `turns`.
+///
+/// `rejected` are the versions `answer` replaced, oldest first.
+pub async fn ask(
+    request: &AnnotationRequest,
+    rejected: &[String],
+    answer: &str,
+    turns: &[Turn],
+    question: &str,
+) -> Result<Annotation, YaYaError> {
+    let turns = turns
+        .iter()
+        .map(|turn| {
+            json::object! {
+                question: turn.question.as_str(),
+                answer: turn.answer.annotation.as_str()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let body = json::object! {
+        kind: request.kind.name(),
+        content: request.content.as_str(),
+        context: request.context.as_str(),
+        rejected: rejected.to_vec(),
+        answer: answer,
+        turns: turns,
+        question: question
+    };
+    let text = post("chat", body).await?;
+
+    Ok(serde_json::from_str(text.as_str())?)
+}
+
+pub async fn success_record(id: usize, result: bool) -> Result<(), YaYaError> {
+    let body = json::object! {
+        id: id,
+        result: result
+    };
+    post("success-record", body).await?;
+
+    Ok(())
+}
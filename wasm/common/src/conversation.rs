@@ -0,0 +1,190 @@
+use leptos::*;
+
+use super::{
+    annotation::{success_record, Annotation, AnnotationRequest, Turn},
+    error::YaYaError,
+    loading::Loading,
+    sanitize::render_annotation,
+};
+
+/// A question of the thread, with its answer once it arrives.
+#[derive(Debug, Clone, PartialEq)]
+struct Exchange {
+    question: String,
+    answer: Option<Result<Annotation, YaYaError>>,
+    /// Feedback was given for the answer.
+    rated: bool,
+}
+
+/// Follow-up questions about `answer`, asked through the chat endpoint and shown inline.
+///
+/// Every answer has its own feedback. A new question can be asked once the last one is answered.
+#[component]
+pub fn Conversation(
+    #[prop(into)] request: MaybeSignal<AnnotationRequest>,
+    /// The explanation the questions are about.
+    answer: Annotation,
+    /// Earlier versions `answer` replaced, oldest first.
+    #[prop(optional, into)]
+    rejected: MaybeSignal<Vec<String>>,
+) -> impl IntoView {
+    let request = Signal::derive(move || request.get());
+    let thread = create_rw_signal(Vec::<Exchange>::new());
+    let (question, set_question) = create_signal(String::new());
+
+    let ask_action = create_action(
+        move |(at, request, question): &(usize, AnnotationRequest, String)| {
+            let at = *at;
+            let turns = thread.with_untracked(|thread| {
+                thread[..at]
+                    .iter()
+                    .filter_map(|e| match &e.answer {
+                        Some(Ok(answer)) => Some(Turn {
+                            question: e.question.clone(),
+                            answer: answer.clone(),
+                        }),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            });
+            let request = request.clone().ask(
+                rejected.get_untracked(),
+                answer.annotation.clone(),
+                turns,
+                question.clone(),
+            );
+            async move { (at, request.await) }
+        },
+    );
+
+    let ask_value = ask_action.value();
+    create_render_effect(move |_| {
+        if let Some((at, answer)) = ask_value.get() {
+            thread.update(|thread| {
+                if let Some(exchange) = thread.get_mut(at) {
+                    exchange.answer = Some(answer);
+                }
+            });
+        }
+    });
+
+    let ask = move |at: usize| {
+        let Some(question) = thread.with_untracked(|t| t.get(at).map(|e| e.question.clone()))
+        else {
+            return;
+        };
+        log::debug!("conversation.rs :: Asking a follow-up: {:?}", question);
+        thread.update(|thread| thread[at].answer = None);
+        ask_action.dispatch((at, request.get_untracked(), question));
+    };
+
+    let can_ask = Signal::derive(move || {
+        thread.with(|thread| {
+            thread
+                .last()
+                .map(|e| matches!(e.answer, Some(Ok(_))))
+                .unwrap_or(true)
+        })
+    });
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let text = question.get_untracked().trim().to_string();
+        if text.is_empty() || !can_ask.get_untracked() {
+            return;
+        }
+
+        let at = thread.with_untracked(|t| t.len());
+        thread.update(|thread| {
+            thread.push(Exchange {
+                question: text,
+                answer: None,
+                rated: false,
+            })
+        });
+        set_question.set(String::new());
+        ask(at);
+    };
+
+    let rate = move |at: usize, good: bool| {
+        let Some(Some(Ok(answer))) = thread.with_untracked(|t| t.get(at).map(|e| e.answer.clone()))
+        else {
+            return;
+        };
+        thread.update(|thread| thread[at].rated = true);
+        spawn_local(async move {
+            if let Err(err) = success_record(answer.id, good).await {
+                log::error!("conversation.rs :: Failed to record feedback: {:?}", err);
+            }
+        });
+    };
+
+    view! {
+        <div class="ya-ya-conversation">
+            <For
+                each=move || { (0..thread.with(|t| t.len())).collect::<Vec<_>>() }
+                key=|at| *at
+                let:at
+            >
+                {
+                    let exchange = create_memo(move |_| thread.with(|t| t.get(at).cloned()));
+                    view! {
+                        <div class="ya-ya-turn">
+                            <p class="ya-ya-question">
+                                {move || exchange.get().map(|e| e.question)}
+                            </p>
+                            {move || match exchange.get().and_then(|e| e.answer.map(|a| (a, e.rated))) {
+                                None => view! { <Loading/> }.into_view(),
+                                Some((Err(err), _)) => view! {
+                                    <div class="ya-ya-turn-error">
+                                        <p>{err.to_string()}</p>
+                                        <button class="ya-ya-button" on:click=move |_| ask(at)>
+                                            "↺ Попробовать ещё раз"
+                                        </button>
+                                    </div>
+                                }.into_view(),
+                                Some((Ok(answer), rated)) => view! {
+                                    <pre class="ya-ya-pre" inner_html=render_annotation(&answer.annotation)/>
+                                    <div class="ya-ya-turn-footer">
+                                        <button
+                                            class="ya-ya-button"
+                                            on:click=move |_| rate(at, false)
+                                            disabled=rated
+                                        >
+                                            "Не понятно"
+                                        </button>
+                                        <button
+                                            class="ya-ya-button"
+                                            on:click=move |_| rate(at, true)
+                                            disabled=rated
+                                        >
+                                            "Ясно"
+                                        </button>
+                                    </div>
+                                }.into_view(),
+                            }}
+                        </div>
+                    }
+                }
+            </For>
+            <form class="ya-ya-follow-up" on:submit=on_submit>
+                <input
+                    type="text"
+                    class="ya-ya-follow-up-input"
+                    placeholder="Спросите ещё: «а пример?»"
+                    aria-label="Уточняющий вопрос"
+                    prop:value=question
+                    on:input=move |ev| set_question.set(event_target_value(&ev))
+                    disabled=move || !can_ask.get()
+                />
+                <button
+                    type="submit"
+                    class="ya-ya-button"
+                    disabled=move || !can_ask.get() || question.with(|q| q.trim().is_empty())
+                >
+                    "Спросить"
+                </button>
+            </form>
+        </div>
+    }
+}
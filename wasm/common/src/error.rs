@@ -1,7 +1,7 @@
 use leptos::*;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, Error, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Error, PartialEq)]
 pub enum YaYaError {
     #[error("Что-то пошло не так. Попробуйте ещё раз.")]
     ClientError,
@@ -52,7 +52,7 @@ pub fn ErrorView(
     });
 
     view! {
-        <div class="ya-ya-error-view">
+        <div class="ya-ya-error-view" role="alert">
             <svg
                 width="100"
                 height="100"
@@ -1,4 +1,14 @@
 pub mod annotation;
+pub mod card;
+pub mod client;
+pub mod conversation;
 pub mod env;
 pub mod error;
 pub mod loading;
+pub mod message;
+pub mod sanitize;
+pub mod sentences;
+pub mod settings;
+pub mod speech;
+pub mod storage;
+pub mod theme;
@@ -0,0 +1,524 @@
+use std::future::Future;
+
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+use super::{
+    annotation::{Annotation, AnnotationRequest, RequestKind, Turn},
+    error::YaYaError,
+};
+
+/// Key of the envelope every message travels in, other messages are left to other listeners.
+const ENVELOPE: &str = "yaYa";
+
+/// A message with the type of its response.
+pub trait Message: Serialize + DeserializeOwned + Into<Request> {
+    type Response: Serialize + DeserializeOwned;
+
+    /// Answers this message, see [`listen`].
+    fn reply(&self, response: Self::Response) -> Reply {
+        Reply(serde_json::to_string(&response).ok())
+    }
+}
+
+/// Text the reader selected on the page.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PageSelection {
+    pub text: String,
+    /// Origin of the page, the context of the text.
+    pub origin: String,
+}
+
+/// Asks the content script for the selected text.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct GetSelection;
+
+impl Message for GetSelection {
+    /// `None` when nothing is selected.
+    type Response = Option<PageSelection>;
+}
+
+/// Asks the content script to highlight the difficult words of the page.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ScanDifficultWords;
+
+impl Message for ScanDifficultWords {
+    type Response = ();
+}
+
+/// Asks the content script to open the reader view, the simplified article next to the original.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct OpenReader;
+
+impl Message for OpenReader {
+    type Response = ();
+}
+
+/// What the reader asked to explain, from the context menu or with a keyboard command.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExplainTarget {
+    Selection,
+    /// The word under the pointer, however common it is.
+    Word,
+    /// Text of a link.
+    Link,
+    /// Description of an image, its `alt` text.
+    Image,
+    /// The paragraph under the pointer, simplified in place.
+    Paragraph,
+}
+
+impl ExplainTarget {
+    pub const ALL: [ExplainTarget; 5] = [
+        ExplainTarget::Selection,
+        ExplainTarget::Word,
+        ExplainTarget::Link,
+        ExplainTarget::Image,
+        ExplainTarget::Paragraph,
+    ];
+
+    /// Id of the context-menu item and name of the keyboard command, see `manifest.json`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExplainTarget::Selection => "explain-selection",
+            ExplainTarget::Word => "explain-word",
+            ExplainTarget::Link => "explain-link",
+            ExplainTarget::Image => "explain-image",
+            ExplainTarget::Paragraph => "simplify-paragraph",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+}
+
+/// Asks the content script to explain the target in place.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Explain {
+    pub target: ExplainTarget,
+}
+
+impl Message for Explain {
+    type Response = ();
+}
+
+/// An explanation asked for on a page, as the side panel lists it.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Explanation {
+    /// Id of the trigger on the page, see [`Reveal`].
+    pub id: String,
+    pub kind: RequestKind,
+    pub content: String,
+    pub context: String,
+    /// The shown answer, `None` while it is requested or when it failed.
+    pub answer: Option<String>,
+    /// Whether the reader found the answer clear, `None` before they said.
+    pub rating: Option<bool>,
+    /// The mark is still on the page, the closed unclear ones are removed.
+    pub on_page: bool,
+}
+
+/// Asks the content script for the explanations of its page, oldest first.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct GetExplanations;
+
+impl Message for GetExplanations {
+    type Response = Vec<Explanation>;
+}
+
+/// Tells the side panel the explanations of the sender's page changed.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct ExplanationsChanged {
+    pub explanations: Vec<Explanation>,
+}
+
+impl Message for ExplanationsChanged {
+    type Response = ();
+}
+
+/// Asks the content script to scroll to the mark of an explanation and flash it.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Reveal {
+    pub id: String,
+}
+
+impl Message for Reveal {
+    type Response = ();
+}
+
+/// Asks the background for an explanation, see [`AnnotationRequest::send`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Annotate {
+    pub request: AnnotationRequest,
+    /// Explanations the reader did not understand, oldest first.
+    pub rejected: Vec<String>,
+}
+
+impl Message for Annotate {
+    type Response = Result<Annotation, YaYaError>;
+}
+
+/// Asks the background a follow-up question, see [`AnnotationRequest::ask`].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Ask {
+    pub request: AnnotationRequest,
+    pub rejected: Vec<String>,
+    pub answer: String,
+    pub turns: Vec<Turn>,
+    pub question: String,
+}
+
+impl Message for Ask {
+    type Response = Result<Annotation, YaYaError>;
+}
+
+/// Tells the background whether an annotation was clear.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RecordSuccess {
+    pub id: usize,
+    pub result: bool,
+}
+
+impl Message for RecordSuccess {
+    type Response = Result<(), YaYaError>;
+}
+
+/// Every message, as a listener receives it.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Request {
+    GetSelection(GetSelection),
+    ScanDifficultWords(ScanDifficultWords),
+    OpenReader(OpenReader),
+    Explain(Explain),
+    GetExplanations(GetExplanations),
+    ExplanationsChanged(ExplanationsChanged),
+    Reveal(Reveal),
+    Annotate(Annotate),
+    Ask(Ask),
+    RecordSuccess(RecordSuccess),
+}
+
+impl From<GetSelection> for Request {
+    fn from(message: GetSelection) -> Self {
+        Self::GetSelection(message)
+    }
+}
+
+impl From<ScanDifficultWords> for Request {
+    fn from(message: ScanDifficultWords) -> Self {
+        Self::ScanDifficultWords(message)
+    }
+}
+
+impl From<OpenReader> for Request {
+    fn from(message: OpenReader) -> Self {
+        Self::OpenReader(message)
+    }
+}
+
+impl From<Explain> for Request {
+    fn from(message: Explain) -> Self {
+        Self::Explain(message)
+    }
+}
+
+impl From<GetExplanations> for Request {
+    fn from(message: GetExplanations) -> Self {
+        Self::GetExplanations(message)
+    }
+}
+
+impl From<ExplanationsChanged> for Request {
+    fn from(message: ExplanationsChanged) -> Self {
+        Self::ExplanationsChanged(message)
+    }
+}
+
+impl From<Reveal> for Request {
+    fn from(message: Reveal) -> Self {
+        Self::Reveal(message)
+    }
+}
+
+impl From<Annotate> for Request {
+    fn from(message: Annotate) -> Self {
+        Self::Annotate(message)
+    }
+}
+
+impl From<Ask> for Request {
+    fn from(message: Ask) -> Self {
+        Self::Ask(message)
+    }
+}
+
+impl From<RecordSuccess> for Request {
+    fn from(message: RecordSuccess) -> Self {
+        Self::RecordSuccess(message)
+    }
+}
+
+/// The serialized response to a [`Request`], made by [`Message::reply`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply(Option<String>);
+
+/// Where a [`Request`] came from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sender {
+    /// Tab of the content script that sent the request, `None` for the extension's pages.
+    pub tab_id: Option<i32>,
+    /// Address of the page or of the frame that sent the request.
+    pub url: Option<String>,
+}
+
+impl Sender {
+    fn from_js(sender: &JsValue) -> Self {
+        let tab = Reflect::get(sender, &"tab".into()).unwrap_or(JsValue::UNDEFINED);
+
+        Self {
+            tab_id: Reflect::get(&tab, &"id".into())
+                .ok()
+                .and_then(|id| id.as_f64())
+                .map(|id| id as i32),
+            url: Reflect::get(sender, &"url".into())
+                .ok()
+                .and_then(|url| url.as_string()),
+        }
+    }
+}
+
+/// The WebExtension API: `browser` in Firefox, `chrome` in Chromium.
+///
+/// Pages have a `chrome` object too, only the one with `runtime` is the extension's.
+fn extension_api() -> Result<JsValue, YaYaError> {
+    let global = js_sys::global();
+
+    ["browser", "chrome"]
+        .into_iter()
+        .filter_map(|name| Reflect::get(&global, &name.into()).ok())
+        .find(|api| {
+            api.is_object()
+                && Reflect::get(api, &"runtime".into())
+                    .map(|runtime| runtime.is_object())
+                    .unwrap_or_default()
+        })
+        .ok_or(YaYaError::IntegrationError)
+}
+
+/// Calls the API method at `path`, like `tabs.sendMessage`.
+fn call(path: &str, args: &Array) -> Result<JsValue, YaYaError> {
+    let mut this = extension_api()?;
+    let mut names = path.split('.').peekable();
+
+    while let Some(name) = names.next() {
+        let value = Reflect::get(&this, &name.into()).map_err(|_| YaYaError::IntegrationError)?;
+        if names.peek().is_none() {
+            let method = value
+                .dyn_into::<Function>()
+                .map_err(|_| YaYaError::IntegrationError)?;
+            return method
+                .apply(&this, args)
+                .map_err(|_| YaYaError::IntegrationError);
+        }
+        this = value;
+    }
+
+    Err(YaYaError::IntegrationError)
+}
+
+async fn call_async(path: &str, args: &Array) -> Result<JsValue, YaYaError> {
+    let promise = call(path, args)?
+        .dyn_into::<Promise>()
+        .map_err(|_| YaYaError::IntegrationError)?;
+
+    JsFuture::from(promise).await.map_err(|err| {
+        // Mostly "Receiving end does not exist": no content script on the page.
+        log::warn!("message.rs :: {path} failed: {err:?}");
+        YaYaError::ClientError
+    })
+}
+
+fn envelope<M: Message>(message: M) -> Result<JsValue, YaYaError> {
+    let request: Request = message.into();
+    let request = serde_json::to_string(&request)?;
+    let envelope = Object::new();
+    Reflect::set(&envelope, &ENVELOPE.into(), &request.into())
+        .map_err(|_| YaYaError::IntegrationError)?;
+
+    Ok(envelope.into())
+}
+
+fn response<M: Message>(response: JsValue) -> Result<M::Response, YaYaError> {
+    let response = response.as_string().ok_or(YaYaError::IntegrationError)?;
+
+    Ok(serde_json::from_str(&response)?)
+}
+
+/// Sends `message` to the extension's pages and the background.
+pub async fn send<M: Message>(message: M) -> Result<M::Response, YaYaError> {
+    let args = Array::of1(&envelope(message)?);
+
+    response::<M>(call_async("runtime.sendMessage", &args).await?)
+}
+
+/// Sends `message` to the content script of a tab.
+pub async fn send_to_tab<M: Message>(tab_id: i32, message: M) -> Result<M::Response, YaYaError> {
+    let args = Array::of2(&tab_id.into(), &envelope(message)?);
+
+    response::<M>(call_async("tabs.sendMessage", &args).await?)
+}
+
+/// Id of the active tab of the current window.
+pub async fn active_tab_id() -> Result<i32, YaYaError> {
+    let query = Object::new();
+    for key in ["active", "currentWindow"] {
+        Reflect::set(&query, &key.into(), &true.into()).map_err(|_| YaYaError::IntegrationError)?;
+    }
+
+    let tabs = call_async("tabs.query", &Array::of1(&query)).await?;
+    Reflect::get(&Array::from(&tabs).get(0), &"id".into())
+        .ok()
+        .and_then(|id| id.as_f64())
+        .map(|id| id as i32)
+        .ok_or(YaYaError::ClientError)
+}
+
+/// Sends `message` to the content script of the active tab of the current window.
+pub async fn send_to_active_tab<M: Message>(message: M) -> Result<M::Response, YaYaError> {
+    send_to_tab(active_tab_id().await?, message).await
+}
+
+/// The request in `message`, when it is one of ours.
+fn incoming(message: &JsValue) -> Option<Request> {
+    let request = Reflect::get(message, &ENVELOPE.into()).ok()?.as_string()?;
+
+    serde_json::from_str::<Request>(&request)
+        .inspect_err(|err| log::warn!("message.rs :: Dropping unknown message: {err}"))
+        .ok()
+}
+
+fn respond(send_response: &Function, Reply(reply): Reply) {
+    let reply = reply.map(JsValue::from).unwrap_or(JsValue::UNDEFINED);
+    _ = send_response.call1(&JsValue::NULL, &reply);
+}
+
+/// `runtime.onMessage` listener, returns whether the response comes later.
+type Listener = dyn Fn(JsValue, JsValue, Function) -> JsValue;
+
+fn add_listener(listener: Closure<Listener>) -> Result<(), YaYaError> {
+    // Listens for as long as the page lives.
+    call(
+        "runtime.onMessage.addListener",
+        &Array::of1(&listener.into_js_value()),
+    )
+    .map(|_| ())
+}
+
+/// Answers the [`Request`]s sent to this context with `handler`, for as long as the page lives.
+///
+/// Requests the handler returns `None` for are left to other listeners.
+pub fn listen(
+    handler: impl Fn(Request, Sender) -> Option<Reply> + 'static,
+) -> Result<(), YaYaError> {
+    add_listener(Closure::<Listener>::new(
+        move |message: JsValue, sender: JsValue, send_response: Function| {
+            let Some(request) = incoming(&message) else {
+                return JsValue::FALSE;
+            };
+
+            log::debug!("message.rs :: Answering {request:?}");
+            if let Some(reply) = handler(request, Sender::from_js(&sender)) {
+                respond(&send_response, reply);
+            }
+
+            JsValue::FALSE
+        },
+    ))
+}
+
+/// Like [`listen`], with replies that take a while, like the ones over the network.
+pub fn listen_async<F>(
+    handler: impl Fn(Request, Sender) -> Option<F> + 'static,
+) -> Result<(), YaYaError>
+where
+    F: Future<Output = Reply> + 'static,
+{
+    add_listener(Closure::<Listener>::new(
+        move |message: JsValue, sender: JsValue, send_response: Function| {
+            let Some(request) = incoming(&message) else {
+                return JsValue::FALSE;
+            };
+
+            log::debug!("message.rs :: Answering {request:?}");
+            match handler(request, Sender::from_js(&sender)) {
+                Some(reply) => {
+                    spawn_local(async move { respond(&send_response, reply.await) });
+                    // Keeps `send_response` valid until the reply is ready.
+                    JsValue::TRUE
+                }
+                None => JsValue::FALSE,
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_tagged() {
+        let request = serde_json::to_string(&Request::from(ScanDifficultWords)).unwrap();
+        assert_eq!(request, r#"{"type":"scan-difficult-words"}"#);
+
+        let request = serde_json::from_str::<Request>(r#"{"type":"get-selection"}"#).unwrap();
+        assert_eq!(request, Request::GetSelection(GetSelection));
+    }
+
+    #[test]
+    fn replies_match_responses() {
+        let selection = PageSelection {
+            text: "Синхротрон".to_string(),
+            origin: "https://ru.wikipedia.org".to_string(),
+        };
+        let Reply(reply) = GetSelection.reply(Some(selection.clone()));
+
+        let response = serde_json::from_str::<<GetSelection as Message>::Response>(&reply.unwrap());
+        assert_eq!(response.unwrap(), Some(selection));
+        assert_eq!(
+            ScanDifficultWords.reply(()),
+            Reply(Some("null".to_string()))
+        );
+    }
+
+    #[test]
+    fn explain_targets_round_trip() {
+        for target in ExplainTarget::ALL {
+            assert_eq!(ExplainTarget::from_name(target.name()), Some(target));
+        }
+
+        let request = serde_json::to_string(&Request::from(Explain {
+            target: ExplainTarget::Link,
+        }))
+        .unwrap();
+        assert_eq!(request, r#"{"type":"explain","target":"link"}"#);
+    }
+
+    #[test]
+    fn errors_cross_the_boundary() {
+        let Reply(reply) = RecordSuccess {
+            id: 7,
+            result: true,
+        }
+        .reply(Err(YaYaError::ServerError));
+
+        let response =
+            serde_json::from_str::<<RecordSuccess as Message>::Response>(&reply.unwrap());
+        assert_eq!(response.unwrap(), Err(YaYaError::ServerError));
+    }
+}
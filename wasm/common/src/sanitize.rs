@@ -0,0 +1,512 @@
+use std::cell::Cell;
+
+use markdown::{mdast::Node, ParseOptions};
+
+use super::sentences::sentence_breaks;
+
+/// Tags the renderer may emit, everything else is rendered as text or dropped.
+pub const ALLOWED_TAGS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "strong",
+    "em",
+    "del",
+    "code",
+    "pre",
+    "ul",
+    "ol",
+    "li",
+    "blockquote",
+    "hr",
+    "br",
+    "a",
+    "table",
+    "thead",
+    "tbody",
+    "tr",
+    "th",
+    "td",
+    "span",
+];
+
+/// Attributes the renderer may emit, per tag.
+pub const ALLOWED_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("a", "title"),
+    ("a", "target"),
+    ("a", "rel"),
+    ("ol", "start"),
+    ("span", SENTENCE_ATTRIBUTE),
+];
+
+/// Index of the sentence a piece of annotation text belongs to, see [`render_annotation`].
+pub const SENTENCE_ATTRIBUTE: &str = "data-ya-ya-sentence";
+
+/// Link schemes kept in `href`, links with other schemes are rendered as their text.
+const ALLOWED_SCHEMES: &[&str] = &["http:", "https:", "mailto:"];
+
+/// Renders model output as HTML safe for `inner_html`.
+///
+/// The markdown is parsed and only the allowlisted tags and attributes are
+/// written out. Raw HTML in the source is shown as text, images become their
+/// alt text and links open in a new tab without access to the page.
+pub fn render_markdown(source: &str) -> String {
+    render(source, false)
+}
+
+/// Like [`render_markdown`], with every sentence of running text on its own line.
+///
+/// The text of every sentence is wrapped in spans with its index in [`SENTENCE_ATTRIBUTE`],
+/// counting from 0 in reading order. Headings and table cells are sentences of their own.
+pub fn render_annotation(source: &str) -> String {
+    render(source, true)
+}
+
+fn render(source: &str, sentences: bool) -> String {
+    let mut out = String::new();
+    let counter = Counter::default();
+    let flow = Flow {
+        sentences,
+        breaks: false,
+        next: None,
+        counter: &counter,
+    };
+
+    match markdown::to_mdast(source, &ParseOptions::default()) {
+        Ok(root) => render_node(&root, &mut out, flow),
+        Err(_) => push_paragraphs(source, &mut out),
+    }
+
+    out
+}
+
+/// Sentences numbered so far.
+#[derive(Debug, Default)]
+struct Counter {
+    started: Cell<usize>,
+    /// The last sentence has text and further text belongs to it.
+    open: Cell<bool>,
+}
+
+impl Counter {
+    /// Index of the sentence the next text belongs to.
+    fn current(&self) -> usize {
+        if !self.open.replace(true) {
+            self.started.set(self.started.get() + 1);
+        }
+        self.started.get() - 1
+    }
+
+    fn end(&self) {
+        self.open.set(false);
+    }
+}
+
+/// Where in the document a node is rendered.
+#[derive(Debug, Clone, Copy)]
+struct Flow<'a> {
+    /// Sentences of running text go on separate lines.
+    sentences: bool,
+    /// The node is running text, where sentences may be broken.
+    breaks: bool,
+    /// First char after the node in the same block.
+    next: Option<char>,
+    counter: &'a Counter,
+}
+
+impl Flow<'_> {
+    fn block(self, breaks: bool) -> Self {
+        self.counter.end();
+        Self {
+            breaks: self.sentences && breaks,
+            next: None,
+            ..self
+        }
+    }
+
+    fn inline(self, breaks: bool) -> Self {
+        Self {
+            breaks: self.breaks && breaks,
+            ..self
+        }
+    }
+}
+
+/// Fallback for input the parser rejects: plain escaped paragraphs.
+fn push_paragraphs(source: &str, out: &mut String) {
+    for paragraph in source.split("\n\n").filter(|p| !p.trim().is_empty()) {
+        open(out, "p", &[]);
+        escape_into(paragraph.trim(), out);
+        close(out, "p");
+    }
+}
+
+fn first_char(node: &Node) -> Option<char> {
+    match node {
+        Node::Text(text) => text.value.chars().next(),
+        Node::InlineCode(code) => code.value.chars().next(),
+        _ => node.children()?.first().and_then(first_char),
+    }
+}
+
+fn render_children(node: &Node, out: &mut String, flow: Flow) {
+    if let Some(children) = node.children() {
+        for (at, child) in children.iter().enumerate() {
+            let next = children.get(at + 1).and_then(first_char).or(flow.next);
+            render_node(child, out, Flow { next, ..flow });
+        }
+    }
+}
+
+fn wrap(node: &Node, tag: &str, attributes: &[(&str, &str)], out: &mut String, flow: Flow) {
+    open(out, tag, attributes);
+    render_children(node, out, flow);
+    close(out, tag);
+}
+
+fn render_node(node: &Node, out: &mut String, flow: Flow) {
+    match node {
+        Node::Root(_) | Node::Definition(_) => render_children(node, out, flow.block(false)),
+        Node::Paragraph(_) => wrap(node, "p", &[], out, flow.block(true)),
+        Node::Heading(heading) => {
+            let tag =
+                ["h1", "h2", "h3", "h4", "h5", "h6"][(heading.depth.clamp(1, 6) - 1) as usize];
+            wrap(node, tag, &[], out, flow.block(false));
+        }
+        Node::Strong(_) => wrap(node, "strong", &[], out, flow.inline(true)),
+        Node::Emphasis(_) => wrap(node, "em", &[], out, flow.inline(true)),
+        Node::Delete(_) => wrap(node, "del", &[], out, flow.inline(true)),
+        Node::Blockquote(_) => wrap(node, "blockquote", &[], out, flow.block(false)),
+        Node::List(list) => match (list.ordered, list.start) {
+            (true, Some(start)) if start != 1 => wrap(
+                node,
+                "ol",
+                &[("start", start.to_string().as_str())],
+                out,
+                flow.block(false),
+            ),
+            (true, _) => wrap(node, "ol", &[], out, flow.block(false)),
+            (false, _) => wrap(node, "ul", &[], out, flow.block(false)),
+        },
+        Node::ListItem(item) => {
+            open(out, "li", &[]);
+            for child in &item.children {
+                // Tight list items render their paragraphs inline.
+                match child {
+                    Node::Paragraph(_) if !item.spread => {
+                        render_children(child, out, flow.block(true))
+                    }
+                    _ => render_node(child, out, flow.block(false)),
+                }
+            }
+            close(out, "li");
+        }
+        Node::Table(_) => wrap(node, "table", &[], out, flow.block(false)),
+        Node::TableRow(_) => wrap(node, "tr", &[], out, flow.block(false)),
+        Node::TableCell(_) => wrap(node, "td", &[], out, flow.block(false)),
+        Node::ThematicBreak(_) => open(out, "hr", &[]),
+        Node::Break(_) => open(out, "br", &[]),
+        Node::Text(text) if flow.sentences => {
+            let breaks = if flow.breaks {
+                sentence_breaks(&text.value, flow.next)
+            } else {
+                vec![]
+            };
+
+            let mut start = 0;
+            for (from, to) in breaks {
+                push_sentence(&text.value[start..from], out, flow, escape_into);
+                open(out, "br", &[]);
+                flow.counter.end();
+                start = to;
+            }
+            push_sentence(&text.value[start..], out, flow, escape_into);
+        }
+        Node::Text(text) => escape_into(&text.value, out),
+        Node::InlineCode(code) if flow.sentences => {
+            push_sentence(&code.value, out, flow, |value, out| {
+                open(out, "code", &[]);
+                escape_into(value, out);
+                close(out, "code");
+            });
+        }
+        Node::InlineCode(code) => {
+            open(out, "code", &[]);
+            escape_into(&code.value, out);
+            close(out, "code");
+        }
+        Node::Code(code) => {
+            open(out, "pre", &[]);
+            open(out, "code", &[]);
+            escape_into(&code.value, out);
+            close(out, "code");
+            close(out, "pre");
+        }
+        Node::Html(html) => escape_into(&html.value, out),
+        Node::Link(link) => match safe_url(&link.url) {
+            Some(url) => {
+                let mut attributes = vec![
+                    ("href", url),
+                    ("target", "_blank"),
+                    ("rel", "noopener noreferrer"),
+                ];
+                if let Some(title) = link.title.as_deref() {
+                    attributes.push(("title", title));
+                }
+                wrap(node, "a", &attributes, out, flow.inline(false));
+            }
+            None => render_children(node, out, flow.inline(false)),
+        },
+        Node::Image(image) => escape_into(&image.alt, out),
+        Node::ImageReference(image) => escape_into(&image.alt, out),
+        Node::LinkReference(_) => render_children(node, out, flow.inline(false)),
+        Node::InlineMath(math) => escape_into(&math.value, out),
+        Node::Math(math) => {
+            open(out, "pre", &[]);
+            escape_into(&math.value, out);
+            close(out, "pre");
+        }
+        Node::FootnoteDefinition(_)
+        | Node::FootnoteReference(_)
+        | Node::MdxJsxFlowElement(_)
+        | Node::MdxJsxTextElement(_)
+        | Node::MdxjsEsm(_)
+        | Node::MdxTextExpression(_)
+        | Node::MdxFlowExpression(_)
+        | Node::Toml(_)
+        | Node::Yaml(_) => {}
+    }
+}
+
+/// Writes a piece of a sentence in a span numbering the sentence.
+fn push_sentence(text: &str, out: &mut String, flow: Flow, write: impl Fn(&str, &mut String)) {
+    if text.is_empty() {
+        return;
+    }
+
+    let index = flow.counter.current().to_string();
+    open(out, "span", &[(SENTENCE_ATTRIBUTE, index.as_str())]);
+    write(text, out);
+    close(out, "span");
+}
+
+/// The URL when it is absolute with an allowed scheme.
+fn safe_url(url: &str) -> Option<&str> {
+    let trimmed = url.trim();
+    // Browsers ignore control chars and whitespace inside the scheme, so reject them outright.
+    if trimmed.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return None;
+    }
+
+    let lower = trimmed.to_lowercase();
+    ALLOWED_SCHEMES
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+        .then_some(trimmed)
+}
+
+fn open(out: &mut String, tag: &str, attributes: &[(&str, &str)]) {
+    debug_assert!(ALLOWED_TAGS.contains(&tag), "tag {tag} is not allowed");
+
+    out.push('<');
+    out.push_str(tag);
+    for (name, value) in attributes {
+        debug_assert!(
+            ALLOWED_ATTRIBUTES.contains(&(tag, *name)),
+            "attribute {name} is not allowed on {tag}"
+        );
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        escape_into(value, out);
+        out.push('"');
+    }
+    out.push('>');
+}
+
+fn close(out: &mut String, tag: &str) {
+    out.push_str("</");
+    out.push_str(tag);
+    out.push('>');
+}
+
+fn escape_into(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every tag in the output is allowlisted and carries only allowlisted attributes.
+    fn assert_allowlisted(html: &str) {
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').expect("unclosed tag") + start;
+            let tag = &rest[start + 1..end];
+            let tag = tag.strip_prefix('/').unwrap_or(tag);
+            let mut parts = tag.splitn(2, ' ');
+            let name = parts.next().unwrap();
+            assert!(ALLOWED_TAGS.contains(&name), "{name} in {html}");
+
+            let mut attributes = parts.next().unwrap_or_default();
+            while let Some(eq) = attributes.find("=\"") {
+                let attribute = attributes[..eq].trim();
+                assert!(
+                    ALLOWED_ATTRIBUTES.contains(&(name, attribute)),
+                    "{attribute} on {name} in {html}"
+                );
+                let value_end = attributes[eq + 2..].find('"').unwrap() + eq + 2;
+                attributes = &attributes[value_end + 1..];
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+
+    const HOSTILE: &[&str] = &[
+        "<script>alert(1)</script>",
+        "<img src=x onerror=alert(1)>",
+        "text <b onmouseover=alert(1)>bold</b>",
+        "<iframe src=\"https://evil.example\"></iframe>",
+        "[click](javascript:alert(1))",
+        "[click](JaVaScRiPt:alert(1))",
+        "[click](java\tscript:alert(1))",
+        "[click](data:text/html;base64,PHNjcmlwdD4=)",
+        "[click](vbscript:msgbox)",
+        "[click](/relative/path)",
+        "![x](https://evil.example/x.png \"t\" )",
+        "![x\" onerror=\"alert(1)](x)",
+        "[a](https://ok.example \"\\\" onclick=\\\"alert(1)\")",
+        "<style>body{display:none}</style>",
+        "<svg onload=alert(1)>",
+        "```html\n<script>alert(1)</script>\n```",
+        "`<script>`",
+        "<a href=\"javascript:alert(1)\">x</a>",
+        "[ref]\n\n[ref]: javascript:alert(1)",
+        "<!-- comment --><form action=x><input></form>",
+        "&lt;script&gt;",
+        "**<u>under</u>**",
+    ];
+
+    #[test]
+    fn hostile_inputs_stay_inert() {
+        for input in HOSTILE {
+            let html = render_markdown(input);
+            assert_allowlisted(&html);
+
+            let lower = html.to_lowercase();
+            assert!(!lower.contains("<script"), "{input} -> {html}");
+            assert!(!lower.contains("javascript:\""), "{input} -> {html}");
+            assert!(!lower.contains("href=\"javascript"), "{input} -> {html}");
+            assert!(!lower.contains("href=\"data"), "{input} -> {html}");
+            assert!(!lower.contains("href=\"/"), "{input} -> {html}");
+            assert!(!lower.contains("<img"), "{input} -> {html}");
+        }
+    }
+
+    #[test]
+    fn raw_html_is_shown_as_text() {
+        assert_eq!(
+            render_markdown("<script>alert(1)</script>"),
+            "&lt;script&gt;alert(1)&lt;/script&gt;"
+        );
+        assert_eq!(
+            render_markdown("a <b>b</b>"),
+            "<p>a &lt;b&gt;b&lt;/b&gt;</p>"
+        );
+    }
+
+    #[test]
+    fn links_open_in_new_tab() {
+        assert_eq!(
+            render_markdown("[ok](https://example.com/?a=1&b=\"2\")"),
+            "<p><a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\" target=\"_blank\" rel=\"noopener noreferrer\">ok</a></p>"
+        );
+        assert_eq!(render_markdown("[bad](javascript:alert(1))"), "<p>bad</p>");
+    }
+
+    /// The annotation without the sentence spans.
+    fn lines_of(html: &str) -> String {
+        let mut lines = String::new();
+        let mut rest = html;
+        while let Some(start) = rest
+            .find("<span")
+            .into_iter()
+            .chain(rest.find("</span>"))
+            .min()
+        {
+            lines.push_str(&rest[..start]);
+            let end = rest[start..].find('>').unwrap() + start;
+            rest = &rest[end + 1..];
+        }
+        lines.push_str(rest);
+        lines
+    }
+
+    #[test]
+    fn annotation_has_one_sentence_per_line() {
+        assert_eq!(
+            lines_of(&render_annotation(
+                "Первая мысль. **Вторая** мысль! См. рис. 2 и т.д.\n\n## Заголовок. Тоже"
+            )),
+            "<p>Первая мысль.<br><strong>Вторая</strong> мысль!<br>См. рис. 2 и т.д.</p>\
+             <h2>Заголовок. Тоже</h2>"
+        );
+        assert_eq!(
+            lines_of(&render_annotation(
+                "- Один. Два.\n- [Ссылка. Текст](https://example.com)"
+            )),
+            "<ul><li>Один.<br>Два.</li><li><a href=\"https://example.com\" target=\"_blank\" \
+             rel=\"noopener noreferrer\">Ссылка. Текст</a></li></ul>"
+        );
+    }
+
+    #[test]
+    fn annotation_sentences_are_numbered() {
+        assert_eq!(
+            render_annotation("## Слово\n\nРаз. **Два** `три`!\n\n- Четыре"),
+            "<h2><span data-ya-ya-sentence=\"0\">Слово</span></h2>\
+             <p><span data-ya-ya-sentence=\"1\">Раз.</span><br>\
+             <strong><span data-ya-ya-sentence=\"2\">Два</span></strong>\
+             <span data-ya-ya-sentence=\"2\"> </span>\
+             <span data-ya-ya-sentence=\"2\"><code>три</code></span>\
+             <span data-ya-ya-sentence=\"2\">!</span></p>\
+             <ul><li><span data-ya-ya-sentence=\"3\">Четыре</span></li></ul>"
+        );
+        assert!(!render_markdown("Раз. Два.").contains("<span"));
+    }
+
+    #[test]
+    fn hostile_inputs_stay_inert_in_annotations() {
+        for input in HOSTILE {
+            assert_allowlisted(&render_annotation(input));
+        }
+    }
+
+    #[test]
+    fn renders_annotation_template() {
+        let html = render_markdown(
+            "## Слово\n\n**Слово в общем значении** Пояснение\n\n- один\n- два\n\n1. раз",
+        );
+
+        assert_eq!(
+            html,
+            "<h2>Слово</h2><p><strong>Слово в общем значении</strong> Пояснение</p>\
+             <ul><li>один</li><li>два</li></ul><ol><li>раз</li></ol>"
+        );
+    }
+}
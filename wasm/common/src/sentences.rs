@@ -0,0 +1,167 @@
+/// Words that end with a period without ending the sentence, lowercase, without the period.
+///
+/// Enumeration closers like "т.д." and "и др." are left out: followed by a capital
+/// letter they end the sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "т.е", "т.к", "т.н", "и.о", "см", "ср", "г", "гг", "в", "вв", "ул", "д", "стр", "рис", "им",
+    "проф", "акад", "руб", "коп", "тыс", "млн", "млрд", "e.g", "i.e", "vs", "dr", "mr", "mrs",
+    "ms", "st",
+];
+
+const SENTENCE_END: &[char] = &['.', '!', '?', '…'];
+
+/// Quotes and brackets that may close a sentence after its final punctuation.
+const CLOSING: &[char] = &['"', '»', '”', '\'', ')'];
+
+/// Where the sentences in `text` end and the next ones start, as byte ranges of the whitespace between them.
+///
+/// `next` is the first char after `text`, when it continues in the next inline node,
+/// so a break at the very end of `text` is found too.
+pub fn sentence_breaks(text: &str, next: Option<char>) -> Vec<(usize, usize)> {
+    let mut breaks = vec![];
+    let chars = text.char_indices().collect::<Vec<_>>();
+
+    let mut at = 0;
+    while at < chars.len() {
+        let (_, c) = chars[at];
+        if !SENTENCE_END.contains(&c) {
+            at += 1;
+            continue;
+        }
+
+        let punctuation_start = at;
+        while at < chars.len() && SENTENCE_END.contains(&chars[at].1) {
+            at += 1;
+        }
+        while at < chars.len() && CLOSING.contains(&chars[at].1) {
+            at += 1;
+        }
+
+        let space_start = at;
+        while at < chars.len() && chars[at].1.is_whitespace() {
+            at += 1;
+        }
+        if space_start == at {
+            continue;
+        }
+
+        let following = chars.get(at).map(|(_, c)| *c).or(next);
+        let starts_sentence = following.map(starts_sentence).unwrap_or(false);
+
+        if starts_sentence && !is_abbreviation(text, &chars, punctuation_start) {
+            let from = chars[space_start].0;
+            let to = chars.get(at).map(|(i, _)| *i).unwrap_or(text.len());
+            breaks.push((from, to));
+        }
+    }
+
+    breaks
+}
+
+/// Splits plain text into sentences, see [`sentence_breaks`].
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = vec![];
+    let mut start = 0;
+
+    for (from, to) in sentence_breaks(text, None) {
+        sentences.push(&text[start..from]);
+        start = to;
+    }
+    let rest = text[start..].trim_end();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+
+    sentences
+}
+
+fn starts_sentence(c: char) -> bool {
+    c.is_uppercase() || c.is_ascii_digit() || matches!(c, '«' | '"' | '„' | '“' | '—' | '–' | '(')
+}
+
+/// Whether the period at `at` ends an abbreviation or an initial.
+fn is_abbreviation(text: &str, chars: &[(usize, char)], at: usize) -> bool {
+    if chars[at].1 != '.' {
+        return false;
+    }
+
+    let word_start = chars[..at]
+        .iter()
+        .rposition(|(_, c)| c.is_whitespace() || matches!(c, '(' | '«' | '"'))
+        .map(|p| p + 1)
+        .unwrap_or(0);
+    let word = &text[chars.get(word_start).map(|(i, _)| *i).unwrap_or(0)..chars[at].0];
+
+    let mut letters = word.chars();
+    let is_initial = matches!(
+        (letters.next(), letters.next()),
+        (Some(first), None) if first.is_uppercase()
+    );
+
+    is_initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &[(&str, &[&str])] = &[
+        ("Одна мысль.", &["Одна мысль."]),
+        (
+            "Первая мысль. Вторая мысль! Третья? Четвёртая…",
+            &["Первая мысль.", "Вторая мысль!", "Третья?", "Четвёртая…"],
+        ),
+        (
+            "Так сказал А. С. Пушкин. Это правда.",
+            &["Так сказал А. С. Пушкин.", "Это правда."],
+        ),
+        (
+            "Есть яблоки, груши и т.д. Всё это фрукты.",
+            &["Есть яблоки, груши и т.д.", "Всё это фрукты."],
+        ),
+        (
+            "Смотрите рис. 3 и см. ниже. Там пример.",
+            &["Смотрите рис. 3 и см. ниже.", "Там пример."],
+        ),
+        (
+            "Число 3.14 знают все. Это пи.",
+            &["Число 3.14 знают все.", "Это пи."],
+        ),
+        (
+            "Он сказал: «Привет.» Потом ушёл.",
+            &["Он сказал: «Привет.»", "Потом ушёл."],
+        ),
+        (
+            "Цена выросла на 5%. 10 лет назад было иначе.",
+            &["Цена выросла на 5%.", "10 лет назад было иначе."],
+        ),
+        (
+            "Важно: не путать с другим словом. Например, с этим.",
+            &["Важно: не путать с другим словом.", "Например, с этим."],
+        ),
+        (
+            "строчная буква. не новое предложение.",
+            &["строчная буква. не новое предложение."],
+        ),
+        ("Что?! Не может быть.", &["Что?!", "Не может быть."]),
+        (
+            "Сайт example.com работает.",
+            &["Сайт example.com работает."],
+        ),
+        ("", &[]),
+    ];
+
+    #[test]
+    fn splits_corpus() {
+        for (text, expected) in CORPUS {
+            assert_eq!(&split_sentences(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn break_at_end_uses_next_char() {
+        assert_eq!(sentence_breaks("Конец. ", Some('Н')), vec![(11, 12)]);
+        assert!(sentence_breaks("Конец. ", Some('н')).is_empty());
+        assert!(sentence_breaks("Конец. ", None).is_empty());
+    }
+}
@@ -0,0 +1,396 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+use leptos::*;
+use serde_json::{json, Value};
+
+use super::{error::YaYaError, storage, theme::Theme};
+
+/// Version of the stored settings, bumped with every new entry in [`MIGRATIONS`].
+pub const SCHEMA_VERSION: u32 = 1;
+
+const STORAGE_KEY: &str = "settings";
+/// Where the theme was saved before there were settings.
+const LEGACY_THEME_KEY: &str = "theme";
+
+/// Words within this many top ranks of the bundled list never trigger a mark.
+pub const FREQUENCY_RANKS: RangeInclusive<usize> = 0..=1000;
+pub const HOVER_DELAYS: RangeInclusive<u32> = 0..=1000;
+pub const TRIGGER_DELAYS: RangeInclusive<u32> = 300..=10_000;
+pub const OPEN_POPOVERS: RangeInclusive<usize> = 1..=10;
+pub const DIFFICULT_WORDS: RangeInclusive<usize> = 1..=50;
+
+/// Upgrades of the stored settings, the one at `n` takes version `n` to `n + 1`.
+const MIGRATIONS: [fn(Value) -> Value; SCHEMA_VERSION as usize] = [from_theme];
+
+/// Version 0 was the theme alone.
+fn from_theme(theme: Value) -> Value {
+    json!({ "theme": theme })
+}
+
+/// How a popover opens again when the caret rests on its mark.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reopen {
+    /// A one-line gloss, expands on click.
+    #[default]
+    Gloss,
+    /// The full explanation.
+    Full,
+}
+
+impl Reopen {
+    pub const ALL: [Reopen; 2] = [Reopen::Gloss, Reopen::Full];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reopen::Gloss => "gloss",
+            Reopen::Full => "full",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Reopen::Gloss => "Кратко",
+            Reopen::Full => "Полностью",
+        }
+    }
+}
+
+/// A number field of [`Settings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    FrequencyRank,
+    HoverDelay,
+    TriggerDelay,
+    MaxOpenPopovers,
+    DifficultWords,
+}
+
+impl Field {
+    pub const ALL: [Field; 5] = [
+        Field::FrequencyRank,
+        Field::HoverDelay,
+        Field::TriggerDelay,
+        Field::MaxOpenPopovers,
+        Field::DifficultWords,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::FrequencyRank => "Не пояснять самые частые слова, сколько",
+            Field::HoverDelay => "Задержка перед выделением слова, мс",
+            Field::TriggerDelay => "Задержка перед пояснением, мс",
+            Field::MaxOpenPopovers => "Сколько пояснений открыто одновременно",
+            Field::DifficultWords => "Сколько сложных слов искать на странице",
+        }
+    }
+}
+
+/// A field out of its range, or not a number at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invalid {
+    pub field: Field,
+    pub message: String,
+}
+
+impl Invalid {
+    pub fn not_a_number(field: Field) -> Self {
+        Self {
+            field,
+            message: "Введите целое число.".to_string(),
+        }
+    }
+
+    fn check<T: PartialOrd + Display>(
+        field: Field,
+        value: T,
+        range: &RangeInclusive<T>,
+    ) -> Option<Self> {
+        (!range.contains(&value)).then(|| Self {
+            field,
+            message: format!("Допустимо от {} до {}.", range.start(), range.end()),
+        })
+    }
+}
+
+/// Everything the reader can tune, shared by the content script, the popup and the options page.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Settings {
+    /// [`SCHEMA_VERSION`] of the stored settings.
+    pub version: u32,
+    pub theme: Theme,
+    /// See [`FREQUENCY_RANKS`].
+    pub frequency_rank: usize,
+    /// How long the caret rests on a word before it is marked, ms.
+    pub hover_delay: u32,
+    /// How long a mark waits before it is explained, ms.
+    pub trigger_delay: u32,
+    pub reopen: Reopen,
+    /// More open popovers close the least recently used ones.
+    pub max_open_popovers: usize,
+    /// How many words the difficult words scan highlights.
+    pub difficult_words: usize,
+    /// Read explanations aloud as soon as they arrive.
+    pub auto_read: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            theme: Theme::default(),
+            frequency_rank: 500,
+            hover_delay: 60,
+            trigger_delay: 1800,
+            reopen: Reopen::default(),
+            max_open_popovers: 3,
+            difficult_words: 12,
+            auto_read: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn get(&self, field: Field) -> u64 {
+        match field {
+            Field::FrequencyRank => self.frequency_rank as u64,
+            Field::HoverDelay => self.hover_delay.into(),
+            Field::TriggerDelay => self.trigger_delay.into(),
+            Field::MaxOpenPopovers => self.max_open_popovers as u64,
+            Field::DifficultWords => self.difficult_words as u64,
+        }
+    }
+
+    /// Sets `field`, saturating values too large for it. See [`Settings::validate`] for the ranges.
+    pub fn set(&mut self, field: Field, value: u64) {
+        let small = u32::try_from(value).unwrap_or(u32::MAX);
+        let size = usize::try_from(value).unwrap_or(usize::MAX);
+        match field {
+            Field::FrequencyRank => self.frequency_rank = size,
+            Field::HoverDelay => self.hover_delay = small,
+            Field::TriggerDelay => self.trigger_delay = small,
+            Field::MaxOpenPopovers => self.max_open_popovers = size,
+            Field::DifficultWords => self.difficult_words = size,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<Invalid>> {
+        let invalid = [
+            Invalid::check(Field::FrequencyRank, self.frequency_rank, &FREQUENCY_RANKS),
+            Invalid::check(Field::HoverDelay, self.hover_delay, &HOVER_DELAYS),
+            Invalid::check(Field::TriggerDelay, self.trigger_delay, &TRIGGER_DELAYS),
+            Invalid::check(
+                Field::MaxOpenPopovers,
+                self.max_open_popovers,
+                &OPEN_POPOVERS,
+            ),
+            Invalid::check(
+                Field::DifficultWords,
+                self.difficult_words,
+                &DIFFICULT_WORDS,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid)
+        }
+    }
+
+    /// The settings with every value moved into its range.
+    pub fn normalized(self) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            theme: self.theme.normalized(),
+            frequency_rank: clamp(self.frequency_rank, &FREQUENCY_RANKS),
+            hover_delay: clamp(self.hover_delay, &HOVER_DELAYS),
+            trigger_delay: clamp(self.trigger_delay, &TRIGGER_DELAYS),
+            max_open_popovers: clamp(self.max_open_popovers, &OPEN_POPOVERS),
+            difficult_words: clamp(self.difficult_words, &DIFFICULT_WORDS),
+            ..self
+        }
+    }
+}
+
+fn clamp<T: Ord + Copy>(value: T, range: &RangeInclusive<T>) -> T {
+    value.clamp(*range.start(), *range.end())
+}
+
+fn version_of(stored: &Value) -> usize {
+    stored
+        .get("version")
+        .and_then(Value::as_u64)
+        .map(|v| v as usize)
+        .unwrap_or_default()
+}
+
+/// Settings from stored JSON of any version, unreadable values fall back to their defaults.
+pub fn migrate(stored: Value) -> Settings {
+    let upgraded = MIGRATIONS
+        .iter()
+        .skip(version_of(&stored))
+        .fold(stored, |value, migration| migration(value));
+
+    serde_json::from_value::<Settings>(upgraded)
+        .inspect_err(|err| log::warn!("settings.rs :: Dropping unreadable settings: {err}"))
+        .unwrap_or_default()
+        .normalized()
+}
+
+/// The saved settings, migrated and saved back when they are outdated.
+pub async fn load_stored() -> Settings {
+    let (stored, key) = match storage::load::<Value>(STORAGE_KEY).await {
+        Some(stored) => (stored, STORAGE_KEY),
+        None => match storage::load::<Value>(LEGACY_THEME_KEY).await {
+            Some(theme) => (theme, LEGACY_THEME_KEY),
+            None => return Settings::default(),
+        },
+    };
+
+    let outdated = key == LEGACY_THEME_KEY || version_of(&stored) < SCHEMA_VERSION as usize;
+    let settings = migrate(stored);
+    if outdated {
+        log::debug!("settings.rs :: Saving the settings migrated from {key}");
+        if let Err(err) = save_settings(settings).await {
+            log::error!("settings.rs :: Failed to save the migrated settings: {err:?}");
+        }
+    }
+
+    settings
+}
+
+/// Migrates and saves back outdated settings whenever they are stored, as when
+/// another device with an older version of the extension syncs them.
+pub fn migrate_on_change() {
+    storage::subscribe(STORAGE_KEY, |changed: Value| {
+        let from = version_of(&changed);
+        if from >= SCHEMA_VERSION as usize {
+            return;
+        }
+
+        let settings = migrate(changed);
+        spawn_local(async move {
+            log::debug!("settings.rs :: Saving the changed settings migrated from version {from}");
+            if let Err(err) = save_settings(settings).await {
+                log::error!("settings.rs :: Failed to save the migrated settings: {err:?}");
+            }
+        });
+    });
+}
+
+/// The saved settings, kept up to date with changes from any page of the extension.
+///
+/// Starts with the defaults until the saved ones are read.
+pub fn load_settings() -> Signal<Settings> {
+    let settings = create_rw_signal(Settings::default());
+
+    spawn_local(async move {
+        let stored = load_stored().await;
+        _ = settings.try_set(stored);
+    });
+    storage::subscribe(STORAGE_KEY, move |changed: Value| {
+        _ = settings.try_set(migrate(changed));
+    });
+
+    settings.into()
+}
+
+pub async fn save_settings(settings: Settings) -> Result<(), YaYaError> {
+    storage::save(STORAGE_KEY, &settings.normalized()).await
+}
+
+/// Makes `settings` the settings of every [`use_settings`] below.
+pub fn provide_settings(settings: Signal<Settings>) {
+    provide_context(settings);
+}
+
+/// The provided settings, or the defaults when there are none.
+pub fn use_settings() -> Signal<Settings> {
+    use_context::<Signal<Settings>>().unwrap_or_else(|| Signal::derive(Settings::default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(Settings::default().validate(), Ok(()));
+        assert_eq!(Settings::default().normalized(), Settings::default());
+    }
+
+    #[test]
+    fn out_of_range_fields_are_reported() {
+        let settings = Settings {
+            trigger_delay: 10,
+            max_open_popovers: 0,
+            ..Settings::default()
+        };
+
+        let fields = settings
+            .validate()
+            .unwrap_err()
+            .into_iter()
+            .map(|i| i.field)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec![Field::TriggerDelay, Field::MaxOpenPopovers]);
+
+        let normalized = settings.normalized();
+        assert_eq!(normalized.trigger_delay, *TRIGGER_DELAYS.start());
+        assert_eq!(normalized.max_open_popovers, 1);
+    }
+
+    #[test]
+    fn fields_round_trip() {
+        let mut settings = Settings::default();
+        for (at, field) in Field::ALL.into_iter().enumerate() {
+            settings.set(field, at as u64 + 7);
+            assert_eq!(settings.get(field), at as u64 + 7);
+        }
+
+        settings.set(Field::HoverDelay, u64::MAX);
+        assert_eq!(settings.hover_delay, u32::MAX);
+    }
+
+    #[test]
+    fn the_theme_alone_migrates() {
+        let settings = migrate(json!({ "palette": "dark", "font_scale": 130 }));
+
+        assert_eq!(settings.version, SCHEMA_VERSION);
+        assert_eq!(settings.theme.font_scale, 130);
+        assert_eq!(settings.frequency_rank, Settings::default().frequency_rank);
+    }
+
+    #[test]
+    fn current_settings_are_kept() {
+        let stored = json!({
+            "version": SCHEMA_VERSION,
+            "theme": { "palette": "high-contrast" },
+            "frequency_rank": 200,
+            "reopen": "full",
+            "auto_read": true,
+        });
+        let settings = migrate(stored);
+
+        assert_eq!(settings.frequency_rank, 200);
+        assert_eq!(settings.reopen, Reopen::Full);
+        assert!(settings.auto_read);
+        assert_eq!(settings.hover_delay, Settings::default().hover_delay);
+    }
+
+    #[test]
+    fn unreadable_settings_fall_back_to_defaults() {
+        let settings = migrate(json!({ "version": SCHEMA_VERSION, "hover_delay": "soon" }));
+
+        assert_eq!(settings, Settings::default());
+    }
+}
@@ -0,0 +1,122 @@
+use leptos::*;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{SpeechSynthesis, SpeechSynthesisUtterance, SpeechSynthesisVoice};
+
+/// Language of the explanations.
+const LANG: &str = "ru-RU";
+
+/// Reads parts of a text aloud with the browser's `speechSynthesis`, one utterance per part.
+#[derive(Debug, Clone, Copy)]
+pub struct Speech {
+    reading: RwSignal<Option<usize>>,
+    /// Bumped on every start and stop, events of cancelled utterances are ignored.
+    generation: StoredValue<usize>,
+}
+
+/// A [`Speech`] that stops its reading when the owner is cleaned up.
+pub fn use_speech() -> Speech {
+    let speech = Speech {
+        reading: create_rw_signal(None),
+        generation: store_value(0),
+    };
+
+    // The queue is shared by the whole page, leave it alone unless this one is reading.
+    let reading = speech.reading;
+    on_cleanup(move || {
+        if reading.try_get_untracked().flatten().is_some() {
+            if let Some(synthesis) = synthesis() {
+                synthesis.cancel();
+            }
+        }
+    });
+
+    speech
+}
+
+fn synthesis() -> Option<SpeechSynthesis> {
+    window().speech_synthesis().ok()
+}
+
+/// A Russian voice, preferring the ones that work offline.
+fn russian_voice(synthesis: &SpeechSynthesis) -> Option<SpeechSynthesisVoice> {
+    let voices = synthesis
+        .get_voices()
+        .iter()
+        .filter_map(|v| v.dyn_into::<SpeechSynthesisVoice>().ok())
+        .filter(|v| v.lang().to_lowercase().starts_with("ru"))
+        .collect::<Vec<_>>();
+
+    voices
+        .iter()
+        .find(|v| v.local_service())
+        .or(voices.first())
+        .cloned()
+}
+
+impl Speech {
+    pub fn is_supported() -> bool {
+        synthesis().is_some()
+    }
+
+    /// Index of the part being read.
+    pub fn reading(&self) -> Signal<Option<usize>> {
+        self.reading.into()
+    }
+
+    /// Reads the parts in order, stopping whatever was read on the page before.
+    pub fn speak(&self, parts: Vec<String>) {
+        let Some(synthesis) = synthesis() else {
+            return;
+        };
+        self.generation.update_value(|g| *g += 1);
+        synthesis.cancel();
+
+        let generation = self.generation.get_value();
+        let voice = russian_voice(&synthesis);
+        let last = parts.len().saturating_sub(1);
+
+        for (at, part) in parts.into_iter().enumerate() {
+            let Ok(utterance) = SpeechSynthesisUtterance::new_with_text(&part) else {
+                continue;
+            };
+            utterance.set_lang(LANG);
+            utterance.set_voice(voice.as_ref());
+
+            let Speech {
+                reading,
+                generation: current,
+            } = *self;
+            let is_current = move || current.try_get_value() == Some(generation);
+
+            // The handlers may fire after the owner is gone, JS owns them from here.
+            let on_start: JsValue = Closure::<dyn FnMut()>::new(move || {
+                if is_current() {
+                    _ = reading.try_set(Some(at));
+                }
+            })
+            .into_js_value();
+            let on_end: JsValue = Closure::<dyn FnMut()>::new(move || {
+                if is_current() && at == last {
+                    _ = reading.try_set(None);
+                }
+            })
+            .into_js_value();
+
+            utterance.set_onstart(Some(on_start.unchecked_ref()));
+            utterance.set_onend(Some(on_end.unchecked_ref()));
+            utterance.set_onerror(Some(on_end.unchecked_ref()));
+            synthesis.speak(&utterance);
+        }
+    }
+
+    /// Stops reading, leaving alone what others read.
+    pub fn stop(&self) {
+        self.generation.update_value(|g| *g += 1);
+        if self.reading.get_untracked().is_some() {
+            if let Some(synthesis) = synthesis() {
+                synthesis.cancel();
+            }
+        }
+        self.reading.set(None);
+    }
+}
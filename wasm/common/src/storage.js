@@ -0,0 +1,34 @@
+const rtm = typeof browser !== "undefined" ? browser : chrome;
+
+// Falls back to the local area where sync is off or over its quota.
+export async function storageGet(key) {
+  try {
+    const items = await rtm.storage.sync.get(key);
+    if (key in items) {
+      return items[key];
+    }
+  } catch (err) {
+    console.warn("storage.sync is unavailable", err);
+  }
+
+  const items = await rtm.storage.local.get(key);
+
+  return items[key];
+}
+
+export async function storageSet(key, value) {
+  try {
+    await rtm.storage.sync.set({ [key]: value });
+  } catch (err) {
+    console.warn("storage.sync is unavailable, saving locally", err);
+    await rtm.storage.local.set({ [key]: value });
+  }
+}
+
+export function onStorageChanged(key, callback) {
+  rtm.storage.onChanged.addListener((changes, area) => {
+    if ((area === "sync" || area === "local") && key in changes) {
+      callback(changes[key].newValue);
+    }
+  });
+}
@@ -0,0 +1,46 @@
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::error::YaYaError;
+
+#[wasm_bindgen(module = "/src/storage.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "storageGet", catch)]
+    async fn storage_get(key: &str) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(js_name = "storageSet", catch)]
+    async fn storage_set(key: &str, value: JsValue) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(js_name = "onStorageChanged")]
+    fn on_storage_changed(key: &str, callback: &JsValue);
+}
+
+/// Reads `key` from the synced storage or the local fallback, `None` when missing or unreadable.
+pub async fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = storage_get(key).await.ok()?.as_string()?;
+
+    serde_json::from_str(&value)
+        .inspect_err(|err| log::warn!("storage.rs :: Dropping unreadable {key}: {err}"))
+        .ok()
+}
+
+pub async fn save<T: Serialize>(key: &str, value: &T) -> Result<(), YaYaError> {
+    let value = serde_json::to_string(value)?;
+    storage_set(key, value.into())
+        .await
+        .map(|_| ())
+        .map_err(|_| YaYaError::ClientError)
+}
+
+/// Calls `on_change` with every new value of `key`, saved by any page of the extension.
+pub fn subscribe<T: DeserializeOwned + 'static>(key: &str, on_change: impl Fn(T) + 'static) {
+    let key_name = key.to_string();
+    let callback = Closure::<dyn Fn(JsValue)>::new(move |value: JsValue| {
+        match value.as_string().map(|v| serde_json::from_str::<T>(&v)) {
+            Some(Ok(value)) => on_change(value),
+            Some(Err(err)) => log::warn!("storage.rs :: Dropping unreadable {key_name}: {err}"),
+            None => {}
+        }
+    });
+
+    // Listens for as long as the page lives.
+    on_storage_changed(key, &callback.into_js_value());
+}
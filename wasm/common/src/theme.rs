@@ -0,0 +1,262 @@
+use leptos::*;
+
+pub const STYLE: &str = include_str!("../../../style.css");
+pub const ANIMATE_STYLE: &str = include_str!("../../../node_modules/animate.css/animate.min.css");
+
+/// The extension's pink, marks and highlights are tinted with it.
+pub const BRAND_COLOR: [u8; 3] = [239, 207, 227];
+/// Marks on the pages when the high contrast palette is on.
+const HIGH_CONTRAST_MARK_COLOR: [u8; 3] = [255, 221, 0];
+
+/// Font sizes the reader can pick from, in percent of the default.
+pub const FONT_SCALES: [u16; 7] = [85, 100, 115, 130, 150, 175, 200];
+
+/// Colors of the popovers and of the extension's pages.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Light or dark following `prefers-color-scheme`, high contrast following `prefers-contrast`.
+    #[default]
+    System,
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::System,
+        Palette::Light,
+        Palette::Dark,
+        Palette::HighContrast,
+    ];
+
+    /// Value of the `data-ya-ya-palette` attribute, see `style.css`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::System => "system",
+            Palette::Light => "light",
+            Palette::Dark => "dark",
+            Palette::HighContrast => "high-contrast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Palette::System => "Как в системе",
+            Palette::Light => "Светлая",
+            Palette::Dark => "Тёмная",
+            Palette::HighContrast => "Высокий контраст",
+        }
+    }
+}
+
+/// Whether the popovers animate.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Motion {
+    /// Following `prefers-reduced-motion`.
+    #[default]
+    System,
+    Reduced,
+    Full,
+}
+
+impl Motion {
+    pub const ALL: [Motion; 3] = [Motion::System, Motion::Reduced, Motion::Full];
+
+    /// Value of the `data-ya-ya-motion` attribute, see `style.css`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Motion::System => "system",
+            Motion::Reduced => "reduced",
+            Motion::Full => "full",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Motion::System => "Как в системе",
+            Motion::Reduced => "Без анимаций",
+            Motion::Full => "С анимациями",
+        }
+    }
+
+    fn animates(&self, prefers_reduced: bool) -> bool {
+        match self {
+            Motion::System => !prefers_reduced,
+            Motion::Reduced => false,
+            Motion::Full => true,
+        }
+    }
+}
+
+/// How the popovers, the popup and the options page look.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Theme {
+    pub palette: Palette,
+    /// Font size in percent of the default, one of [`FONT_SCALES`].
+    pub font_scale: u16,
+    /// OpenDyslexic when it is installed, with wider spacing, easier to read with dyslexia.
+    pub dyslexic_font: bool,
+    pub motion: Motion,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            font_scale: 100,
+            dyslexic_font: false,
+            motion: Motion::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// The theme with the font scale snapped to the closest of [`FONT_SCALES`].
+    pub fn normalized(self) -> Self {
+        let font_scale = FONT_SCALES
+            .into_iter()
+            .min_by_key(|s| s.abs_diff(self.font_scale))
+            .unwrap_or(100);
+
+        Self { font_scale, ..self }
+    }
+
+    /// Whether to play the entrance and loading animations.
+    pub fn animates(&self) -> bool {
+        self.motion.animates(prefers_reduced_motion())
+    }
+
+    /// Inline style of the themed root, see `style.css`.
+    pub fn style(&self) -> String {
+        format!("--ya-ya-font-scale: {}", f64::from(self.font_scale) / 100.0)
+    }
+
+    /// Background of the marks on the pages, which `style.css` does not reach.
+    pub fn mark_color(&self) -> String {
+        let high_contrast = match self.palette {
+            Palette::System => prefers_more_contrast(),
+            Palette::HighContrast => true,
+            Palette::Light | Palette::Dark => false,
+        };
+        let ([r, g, b], alpha) = if high_contrast {
+            (HIGH_CONTRAST_MARK_COLOR, 1.0)
+        } else {
+            (BRAND_COLOR, 0.75)
+        };
+
+        format!("rgba({r}, {g}, {b}, {alpha})")
+    }
+}
+
+fn matches_media(query: &str) -> bool {
+    window()
+        .match_media(query)
+        .ok()
+        .flatten()
+        .map(|list| list.matches())
+        .unwrap_or_default()
+}
+
+fn prefers_reduced_motion() -> bool {
+    matches_media("(prefers-reduced-motion: reduce)")
+}
+
+fn prefers_more_contrast() -> bool {
+    matches_media("(prefers-contrast: more)")
+}
+
+/// Makes `theme` the theme of every [`use_theme`] below.
+pub fn provide_theme(theme: Signal<Theme>) {
+    provide_context(theme);
+}
+
+/// The provided theme, or the default one when there is none.
+pub fn use_theme() -> Signal<Theme> {
+    use_context::<Signal<Theme>>().unwrap_or_else(|| Signal::derive(Theme::default))
+}
+
+/// Root of themed markup, carries the palette, the font and the motion to `style.css`.
+#[component]
+pub fn Themed(
+    #[prop(into)] theme: Signal<Theme>,
+    #[prop(optional)] id: Option<&'static str>,
+    /// Classes besides `ya-ya-theme`.
+    #[prop(optional)]
+    class: &'static str,
+    #[prop(optional)] node_ref: NodeRef<html::Div>,
+    children: Children,
+) -> impl IntoView {
+    provide_theme(theme);
+
+    view! {
+        <div
+            id=id
+            class=format!("ya-ya-theme {class}").trim_end().to_string()
+            data-ya-ya-palette=move || theme.get().palette.name()
+            data-ya-ya-font=move || if theme.get().dyslexic_font { "dyslexic" } else { "default" }
+            data-ya-ya-motion=move || theme.get().motion.name()
+            style=move || theme.get().style()
+            node_ref=node_ref
+        >
+            {children()}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for palette in Palette::ALL {
+            assert_eq!(Palette::from_name(palette.name()), Some(palette));
+        }
+        for motion in Motion::ALL {
+            assert_eq!(Motion::from_name(motion.name()), Some(motion));
+        }
+        assert_eq!(Palette::from_name("sepia"), None);
+    }
+
+    #[test]
+    fn font_scale_snaps_to_the_closest() {
+        let theme = |font_scale| Theme {
+            font_scale,
+            ..Theme::default()
+        };
+
+        assert_eq!(theme(120).normalized().font_scale, 115);
+        assert_eq!(theme(10).normalized().font_scale, 85);
+        assert_eq!(theme(900).normalized().font_scale, 200);
+        assert_eq!(theme(150).normalized().font_scale, 150);
+    }
+
+    #[test]
+    fn reduced_motion_is_honoured() {
+        assert!(!Motion::System.animates(true));
+        assert!(Motion::System.animates(false));
+        assert!(!Motion::Reduced.animates(false));
+        assert!(Motion::Full.animates(true));
+    }
+
+    #[test]
+    fn missing_fields_take_defaults() {
+        let theme: Theme = serde_json::from_str(r#"{"palette":"high-contrast"}"#).unwrap();
+
+        assert_eq!(theme.palette, Palette::HighContrast);
+        assert_eq!(theme.font_scale, 100);
+        assert_eq!(theme.motion, Motion::System);
+    }
+}
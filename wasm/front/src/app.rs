@@ -1,84 +1,209 @@
+mod article;
+mod command;
+mod history;
+mod lexicon;
+mod lifecycle;
 mod mark;
+mod nested;
+mod paragraph;
+mod placement;
 mod popover;
+mod prefetch;
+mod reader;
+mod scan;
+mod stack;
+mod text;
 mod util;
 mod word;
-mod ya_word;
+mod ya_trigger;
 
-use std::collections::HashMap;
+use std::{collections::VecDeque, time::Duration};
 
-use common::annotation::{annotate_word, success_record};
+use article::{extract_article, Article};
+use common::{
+    message::{send, ExplainTarget, ExplanationsChanged},
+    settings::{load_settings, provide_settings},
+    theme::{Themed, ANIMATE_STYLE, STYLE},
+};
 use leptos::*;
 use leptos_use::{
     signal_debounced, use_document, use_event_listener, use_raf_fn, use_window,
     UseRafFnCallbackArgs,
 };
+use lifecycle::{Effect, Event, Lifecycle, Tier};
 use mark::{PendingMark, PermanentTrigger};
+use paragraph::{ParagraphMark, SimplifyButton, PARAGRAPH_SELECTOR};
+use prefetch::{Delivery, Prefetch};
+use reader::ReaderView;
+use scan::{content_root, DifficultWordsScan};
+use stack::PopoverStack;
+use text::TextMark;
 use uuid::Uuid;
-use web_sys::CaretPosition;
-use word::{WordMark, WordPermanentTrigger};
-use ya_word::YaWordPopover;
+use wasm_bindgen::JsCast;
+use web_sys::{CaretPosition, Element, Node};
+use word::WordMark;
+use ya_trigger::{YaGlossPopover, YaTriggerPopover};
 
 use super::MOUNT;
 
-pub const BEFORE_TRIGGER_TIMER: f64 = 60.0;
-pub const TRIGGER_ANIMATED_TIMER: f64 = 1800.0;
+pub use command::{run, Command};
+pub use history::explanations;
+
 pub const MARK_ROOT_ATTRIBUTE: &str = "data-ya-ya-mark-root";
 pub const TRIGGER_ATTRIBUTE_WORD: &str = "data-ya-ya-trigger-word";
 pub const PENDING_ATTRIBUTE_WORD: &str = "data-ya-ya-pending-word";
-pub const BRAND_COLOR: [u8; 3] = [239, 207, 227];
-
-const STYLE: &str = include_str!("../../../style.css");
-const ANIMATE_STYLE: &str = include_str!("../../../node_modules/animate.css/animate.min.css");
+pub const DIFFICULT_ATTRIBUTE_WORD: &str = "data-ya-ya-difficult-word";
+/// Set on a mark the side panel revealed, see `content/styles.css`.
+pub const FLASH_ATTRIBUTE: &str = "data-ya-ya-flash";
+const FLASH_DURATION: Duration = Duration::from_millis(1500);
+/// Set on the root of a paragraph simplified in place, see `content/styles.css`.
+pub const SIMPLIFIED_ATTRIBUTE: &str = "data-ya-ya-simplified";
+/// Set on the "упрощено" toggle of a simplified paragraph, holds its id.
+pub const SIMPLIFIED_TOGGLE_ATTRIBUTE: &str = "data-ya-ya-simplified-toggle";
 
 #[component]
 pub fn App() -> impl IntoView {
     let extension_root = create_node_ref::<html::Div>();
 
-    let (data, set_data) = create_signal(HashMap::<Uuid, PermanentTrigger>::new());
-    let (show_ya, set_show_ya) = create_signal(Vec::<Uuid>::new());
+    let settings = load_settings();
+    provide_settings(settings);
+    let theme = create_memo(move |_| settings.get().theme);
+    let reopen_tier = create_memo(move |_| Tier::from(settings.get().reopen));
+    let auto_read = create_memo(move |_| settings.get().auto_read);
 
-    let (pending_mark, set_pending_mark) = create_signal(Option::<PendingMark>::None);
+    let (lifecycle, set_lifecycle) =
+        create_signal(Lifecycle::<PendingMark, PermanentTrigger>::new(
+            settings.get_untracked().trigger_delay.into(),
+        ));
+    let (difficult, set_difficult) = create_signal(Vec::<WordMark>::new());
+    let prefetch = store_value(Option::<Prefetch>::None);
+    let stack = create_rw_signal(PopoverStack::new(
+        settings.get_untracked().max_open_popovers,
+    ));
+
+    let trigger_delay = create_memo(move |_| settings.get().trigger_delay);
+    create_effect(move |_| {
+        let trigger_after = f64::from(trigger_delay.get());
+        set_lifecycle.update(|l| l.set_trigger_after(trigger_after));
+    });
+    let max_open_popovers = create_memo(move |_| settings.get().max_open_popovers);
+    create_effect(move |_| {
+        let max_open = max_open_popovers.get();
+        stack.update(|s| s.set_max_open(max_open));
+    });
 
     let (caret, set_caret) = create_signal(Option::<CaretPosition>::None);
-    let caret = signal_debounced(caret, BEFORE_TRIGGER_TIMER);
+    let hover_delay = Signal::derive(move || f64::from(settings.get().hover_delay));
+    let caret = signal_debounced(caret, hover_delay);
     let (pointer, set_pointer) = create_signal(false);
 
-    let annotate_action = create_action(
-        |(id, word, ctx, prev): &(Uuid, String, String, Option<String>)| {
-            let ctx = ctx.clone();
-            let word = word.clone();
-            let prev = prev.clone();
-            let id = *id;
-
-            async move {
-                let res = annotate_word(word, ctx, prev).await;
+    let annotate_action = create_action(|(trigger, rejected): &(PermanentTrigger, Vec<String>)| {
+        let id = trigger.id();
+        let request = trigger.annotate(rejected.clone());
 
-                (id, res)
-            }
-        },
-    );
-
-    let success_record_action = create_action(|(id, result): &(usize, bool)| {
-        let id = *id;
-        let result = *result;
         async move {
-            _ = success_record(id, result).await;
+            let res = request.await;
+
+            (id, res)
         }
     });
 
-    let replace_pending = Callback::new(move |mark: Option<PendingMark>| {
-        set_pending_mark.update(|c| {
-            if let Some(old_mark) = c.take() {
-                log::debug!("app.rs :: Removing old PendingMark");
-                old_mark.unmount().unwrap();
+    let success_record_action =
+        create_action(|(trigger, id, result): &(PermanentTrigger, usize, bool)| {
+            let request = trigger.feedback(*id, *result);
+            async move {
+                _ = request.await;
             }
-            *c = mark;
         });
+
+    // Annotated events never produce effects, so prefetches can skip the queue.
+    let deliver: Delivery = Callback::new(move |(id, res)| {
+        set_lifecycle.update(|l| _ = l.reduce(Event::Annotated(id, res)));
+    });
+
+    let dispatch = Callback::new(move |event: Event<PendingMark, PermanentTrigger>| {
+        let mut events = VecDeque::from([event]);
+
+        while let Some(event) = events.pop_front() {
+            let mut effects = vec![];
+            set_lifecycle.update(|l| effects = l.reduce(event));
+
+            for effect in effects {
+                match effect {
+                    Effect::Start => {
+                        log::debug!("app.rs :: Starting PendingMark timer");
+                        if let Some(mark) = lifecycle.with_untracked(|l| l.pending().cloned()) {
+                            _ = mark.start_animation();
+                            log::debug!("app.rs :: Prefetching annotation for PendingMark");
+                            prefetch.set_value(Some(Prefetch::start(mark.request(), deliver)));
+                        }
+                    }
+                    Effect::Unmount(mark) => {
+                        if let Some(old_prefetch) =
+                            prefetch.try_update_value(|p| p.take()).flatten()
+                        {
+                            log::debug!("app.rs :: Aborting prefetch of old PendingMark");
+                            old_prefetch.abort();
+                        }
+                        log::debug!("app.rs :: Removing old PendingMark");
+                        if let Err(err) = mark.unmount() {
+                            log::error!("app.rs :: Failed to unmount PendingMark: {err:?}");
+                        }
+                    }
+                    Effect::Promote(mark) => {
+                        log::debug!("app.rs :: Converting PendingMark to permanent");
+                        let id = Uuid::new_v4();
+                        match mark.make_permanent(id) {
+                            Ok(permanent) => events.push_back(Event::Promoted(id, permanent)),
+                            Err(err) => log::error!("app.rs :: Failed to promote mark: {err:?}"),
+                        }
+                    }
+                    Effect::Annotate { id, rejected } => {
+                        let trigger = if let Some(trigger) =
+                            lifecycle.with_untracked(|l| l.entry(&id).map(|e| e.trigger.clone()))
+                        {
+                            trigger
+                        } else {
+                            continue;
+                        };
+
+                        let prefetched = prefetch
+                            .try_update_value(|p| {
+                                p.take_if(|p| rejected.is_empty() && p.is_for(&trigger.request()))
+                            })
+                            .flatten();
+
+                        if let Some(p) = prefetched {
+                            log::debug!("app.rs :: Reusing prefetch for ID: {:?}", id);
+                            p.adopt(id);
+                        } else {
+                            annotate_action.dispatch((trigger, rejected));
+                        }
+                    }
+                    Effect::Record {
+                        trigger,
+                        annotation,
+                        good,
+                    } => {
+                        success_record_action.dispatch((trigger, annotation, good));
+                    }
+                    Effect::Remove(trigger) => {
+                        if let Err(err) = trigger.unmount() {
+                            log::error!("app.rs :: Failed to unmount PermanentTrigger: {err:?}");
+                        }
+                    }
+                }
+            }
+        }
     });
 
+    // Last position of the mouse, the word under it is explained on command.
+    let pointer_at = store_value(Option::<(f32, f32)>::None);
+
     let clear_mouse_move_listener = use_event_listener(use_window(), ev::mousemove, move |evt| {
         let x = evt.client_x() as f32;
         let y = evt.client_y() as f32;
+        pointer_at.set_value(Some((x, y)));
         let win = web_sys::window().unwrap();
         let doc = win.document().unwrap();
         let car = doc.caret_position_from_point(x, y);
@@ -88,7 +213,7 @@ pub fn App() -> impl IntoView {
                 set_caret.set(car.filter(|car| !root.contains(car.offset_node().as_ref())));
             }
         } else {
-            replace_pending.call(None)
+            dispatch.call(Event::Leave)
         }
     });
 
@@ -101,6 +226,286 @@ pub fn App() -> impl IntoView {
         set_pointer.set(false);
     });
 
+    let scan_difficult_words = move || {
+        set_difficult.update(|d| {
+            for mark in d.drain(..) {
+                _ = mark.unmount();
+            }
+        });
+
+        let settings = settings.get_untracked();
+        match DifficultWordsScan::start(&content_root(), settings.frequency_rank) {
+            Ok(scan) => {
+                log::debug!("app.rs :: Starting difficult words scan");
+                scan.run_idle(
+                    settings.difficult_words,
+                    Callback::new(move |found: Vec<(Node, u32)>| {
+                        log::debug!("app.rs :: Mounting {} difficult words", found.len());
+                        set_difficult.set(
+                            found
+                                .into_iter()
+                                .filter_map(|(node, pos)| {
+                                    WordMark::mount_difficult(node, pos, &settings)
+                                })
+                                .collect(),
+                        );
+                    }),
+                );
+            }
+            Err(err) => log::error!("app.rs :: Failed to start difficult words scan: {err:?}"),
+        }
+    };
+
+    let (article, set_article) = create_signal(Option::<Article>::None);
+    let open_reader = move || match extract_article() {
+        Some(found) => {
+            log::debug!(
+                "app.rs :: Opening the reader view, {} chunks",
+                found.chunks.len()
+            );
+            dispatch.call(Event::Leave);
+            set_article.set(Some(found));
+        }
+        None => log::debug!("app.rs :: No article to read on the page"),
+    };
+
+    // Where the context menu was last opened, the menu items explain what is there.
+    let context_target = store_value(Option::<Element>::None);
+    let clear_context_menu_listener =
+        use_event_listener(use_window(), ev::contextmenu, move |ev| {
+            context_target.set_value(ev.target().and_then(|t| t.dyn_into::<Element>().ok()));
+            pointer_at.set_value(Some((ev.client_x() as f32, ev.client_y() as f32)));
+        });
+
+    // The word under the pointer, right away: an open trigger, the pending or a difficult
+    // mark, or a new mark however common the word is.
+    let chosen_word = move || -> Option<PendingMark> {
+        let caret_at_pointer = || {
+            pointer_at.get_value().and_then(|(x, y)| {
+                let caret = document().caret_position_from_point(x, y)?;
+                Some((caret.offset_node()?, caret.offset()))
+            })
+        };
+        let (node, _) = caret_at_pointer()?;
+
+        if let Some(id) = lifecycle.with_untracked(|l| l.find(|t| t.contains(&node))) {
+            log::debug!("app.rs :: Explaining the trigger with ID: {:?}", id);
+            dispatch.call(Event::Reopen(id, Tier::Full));
+            return None;
+        }
+
+        if lifecycle.with_untracked(|l| l.pending().is_some_and(|p| p.contains(&node))) {
+            log::debug!("app.rs :: Explaining the pending mark without waiting");
+            dispatch.call(Event::Commit);
+            return None;
+        }
+
+        if let Some(at) =
+            difficult.with_untracked(|d| d.iter().position(|m| m.mark.contains(Some(&node))))
+        {
+            let mut hard = None;
+            set_difficult.update(|d| hard = Some(d.remove(at)));
+            return hard.map(PendingMark::new);
+        }
+
+        // Another word may be pending, restore its text before mounting.
+        dispatch.call(Event::Leave);
+        caret_at_pointer().and_then(|(node, offset)| {
+            WordMark::mount_chosen(node, offset, &settings.get_untracked()).map(PendingMark::new)
+        })
+    };
+
+    // Paragraphs simplified in place, the toggle of each puts its original content back.
+    let simplified = store_value(Vec::<ParagraphMark>::new());
+
+    let revert = move |id: Uuid| {
+        let Some(at) = simplified.with_value(|s| s.iter().position(|p| p.id == id)) else {
+            return;
+        };
+        let mut mark = None;
+        simplified.update_value(|s| mark = Some(s.remove(at)));
+
+        // A word of the simplified text may be pending.
+        dispatch.call(Event::Leave);
+        log::debug!("app.rs :: Reverting the paragraph with ID: {:?}", id);
+        if let Some(Err(err)) = mark.map(|m| m.unmount()) {
+            log::error!("app.rs :: Failed to revert the paragraph: {err:?}");
+        }
+    };
+
+    let simplify = move |paragraph: Element| {
+        // A word of the paragraph may be pending.
+        dispatch.call(Event::Leave);
+        let Some(mark) = ParagraphMark::mount(&paragraph) else {
+            return;
+        };
+
+        log::debug!("app.rs :: Simplifying the paragraph with ID: {:?}", mark.id);
+        let request = mark.request().send(vec![]);
+        simplified.update_value(|s| s.push(mark.clone()));
+        spawn_local(async move {
+            let answer = request.await;
+            // The reader may have reverted it already.
+            if simplified.try_with_value(|s| s.contains(&mark)) != Some(true) {
+                return;
+            }
+            if let Err(err) = mark.show(&answer) {
+                log::error!("app.rs :: Failed to show the simplified paragraph: {err:?}");
+            }
+        });
+    };
+
+    let clear_simplified_listener = use_event_listener(use_window(), ev::click, move |ev| {
+        let Some(id) = ev
+            .target()
+            .and_then(|t| t.dyn_into::<Element>().ok())
+            .and_then(|el| {
+                el.closest(format!("[{SIMPLIFIED_TOGGLE_ATTRIBUTE}]").as_str())
+                    .ok()
+                    .flatten()
+            })
+            .and_then(|toggle| toggle.get_attribute(SIMPLIFIED_TOGGLE_ATTRIBUTE))
+            .and_then(|id| Uuid::parse_str(&id).ok())
+        else {
+            return;
+        };
+
+        // The paragraph may be inside a link.
+        ev.prevent_default();
+        revert(id);
+    });
+
+    // Paragraph under the mouse, its `SimplifyButton` is shown next to it.
+    let (offered, set_offered) = create_signal(Option::<Element>::None);
+
+    let clear_offer_listener = use_event_listener(use_window(), ev::mousemove, move |ev| {
+        let Some(target) = ev.target().and_then(|t| t.dyn_into::<Element>().ok()) else {
+            return;
+        };
+        let is_offered =
+            offered.with_untracked(|p| p.as_ref().is_some_and(|p| p.contains(Some(&target))));
+        // The button itself is in the extension root.
+        let is_extension = target
+            .closest(format!("#{MOUNT}").as_str())
+            .ok()
+            .flatten()
+            .is_some();
+        if is_offered || is_extension {
+            return;
+        }
+
+        let paragraph = ParagraphMark::offered(&target);
+        if paragraph != offered.get_untracked() {
+            set_offered.set(paragraph);
+        }
+    });
+
+    let explain = move |target: ExplainTarget| {
+        let closest = |selector: &str| {
+            context_target
+                .get_value()
+                .and_then(|el| el.closest(selector).ok().flatten())
+        };
+
+        let mark = match target {
+            ExplainTarget::Selection => web_sys::window()
+                .and_then(|w| w.get_selection().ok().flatten())
+                .filter(|s| !s.is_collapsed() && s.range_count() > 0)
+                .and_then(|s| {
+                    let range = s.get_range_at(0).ok()?;
+                    TextMark::on_range(&range, s.to_string().into())
+                })
+                .map(PendingMark::new),
+            ExplainTarget::Link => closest("a").and_then(|link| {
+                let text = link
+                    .text_content()
+                    .filter(|t| !t.trim().is_empty())
+                    .or_else(|| link.get_attribute("aria-label"))
+                    .or_else(|| link.get_attribute("title"))?;
+                TextMark::on_element(&link, text).map(PendingMark::new)
+            }),
+            ExplainTarget::Image => closest("img").and_then(|image| {
+                let alt = image
+                    .get_attribute("alt")
+                    .filter(|a| !a.trim().is_empty())
+                    .or_else(|| image.get_attribute("title"))?;
+                TextMark::on_element(&image, alt).map(PendingMark::new)
+            }),
+            ExplainTarget::Word => chosen_word(),
+            ExplainTarget::Paragraph => {
+                let Some(element) = context_target.get_value() else {
+                    return;
+                };
+                let chosen = simplified.with_value(|s| {
+                    s.iter()
+                        .find(|p| p.paragraph.contains(Some(&element)))
+                        .map(|p| p.id)
+                });
+                match chosen {
+                    Some(id) => revert(id),
+                    None => {
+                        if let Some(paragraph) = closest(PARAGRAPH_SELECTOR) {
+                            simplify(paragraph);
+                        }
+                    }
+                }
+                return;
+            }
+        };
+
+        match mark {
+            Some(mark) => {
+                log::debug!("app.rs :: Explaining {target:?}");
+                dispatch.call(Event::Activate(mark));
+            }
+            None => log::debug!("app.rs :: No new mark to explain for {target:?}"),
+        }
+    };
+
+    let reveal = move |id: Uuid| {
+        let Some(trigger) = lifecycle.with_untracked(|l| l.entry(&id).map(|e| e.trigger.clone()))
+        else {
+            log::debug!("app.rs :: Nothing to reveal for ID: {:?}", id);
+            return;
+        };
+
+        let (_, mark, _) = trigger.anchors();
+        let options = web_sys::ScrollIntoViewOptions::new();
+        options.set_behavior(web_sys::ScrollBehavior::Smooth);
+        options.set_block(web_sys::ScrollLogicalPosition::Center);
+        mark.scroll_into_view_with_scroll_into_view_options(&options);
+
+        let flash = if settings.get_untracked().theme.animates() {
+            "animated"
+        } else {
+            "static"
+        };
+        _ = mark.set_attribute(FLASH_ATTRIBUTE, flash);
+        set_timeout(
+            move || _ = mark.remove_attribute(FLASH_ATTRIBUTE),
+            FLASH_DURATION,
+        );
+    };
+
+    command::listen(Callback::new(move |command| match command {
+        Command::ScanDifficultWords => scan_difficult_words(),
+        Command::OpenReader => open_reader(),
+        Command::Explain(target) => explain(target),
+        Command::Reveal(id) => reveal(id),
+    }));
+
+    // The side panel lists the explanations of the page.
+    create_effect(move |_| {
+        let current = lifecycle.with(|l| l.entries().map(history::explanation).collect());
+        if let Some(explanations) = history::sync(current) {
+            spawn_local(async move {
+                if let Err(err) = send(ExplanationsChanged { explanations }).await {
+                    log::debug!("app.rs :: Failed to update the side panel: {err:?}");
+                }
+            });
+        }
+    });
+
     create_effect(move |_| {
         let no_selection = web_sys::window()
             .unwrap()
@@ -110,150 +515,118 @@ pub fn App() -> impl IntoView {
             .map(|s| s.is_collapsed())
             .unwrap_or(true);
 
-        if let Some(id) = caret.get().and_then(|car| {
-            car.offset_node()
-                .and_then(|node| WordPermanentTrigger::id(node.clone()))
-        }) {
+        if let Some(id) = caret
+            .get()
+            .and_then(|car| car.offset_node())
+            .and_then(|node| lifecycle.with_untracked(|l| l.find(|t| t.contains(&node))))
+        {
             log::debug!("app.rs :: Found PermanentTrigger with ID: {:?}", id);
-            set_show_ya.update(|d| {
-                if !d.contains(&id) {
-                    d.push(id);
-                }
-            });
+            dispatch.call(Event::Reopen(id, reopen_tier.get_untracked()));
+        } else if let Some(at) = caret
+            .get()
+            .and_then(|car| car.offset_node())
+            .and_then(|node| {
+                difficult.with_untracked(|d| d.iter().position(|m| m.mark.contains(Some(&node))))
+            })
+        {
+            log::debug!("app.rs :: Found difficult WordMark, skipping the pending timer");
+            let mut hard = None;
+            set_difficult.update(|d| hard = Some(d.remove(at)));
+            dispatch.call(Event::Activate(PendingMark::new(hard.unwrap())));
         } else if let Some(new_wd_mark) = caret.get().filter(|_| no_selection).and_then(|car| {
-            car.offset_node()
-                .and_then(|node| WordMark::mount_on_text(node, car.offset()))
+            car.offset_node().and_then(|node| {
+                WordMark::mount_on_text(node, car.offset(), &settings.get_untracked())
+            })
         }) {
             log::debug!("app.rs :: Mounting new WordMark");
-            replace_pending.call(Some(PendingMark::Word(new_wd_mark)));
+            dispatch.call(Event::Hover(PendingMark::new(new_wd_mark)));
         } else if caret
             .get()
             .filter(|_| no_selection)
             .and_then(|car| {
                 car.offset_node().and_then(|node| {
-                    pending_mark
-                        .with_untracked(|wd| wd.as_ref().map(|wd| !wd.is_same(node, car.offset())))
+                    lifecycle
+                        .with_untracked(|l| l.pending().map(|wd| !wd.is_same(node, car.offset())))
                 })
             })
             .unwrap_or(no_selection)
         {
             log::debug!("clear pendning mark on carret move");
-            replace_pending.call(None);
+            dispatch.call(Event::Leave);
         }
     });
 
     _ = use_raf_fn(move |UseRafFnCallbackArgs { delta, .. }| {
-        if pending_mark.get_untracked().is_some() {
-            set_pending_mark.update(|set_pending_mark| {
-                let wd = set_pending_mark.as_mut().unwrap();
-                log::debug!("app.rs :: Starting tick_timer for WordMark");
-                let ended = wd.tick_timer(delta);
-                if ended {
-                    log::debug!("app.rs :: tick_timer ended, converting WordMark to permanent");
-                    let id = Uuid::new_v4();
-                    let permanent = wd.make_permanent(id).unwrap();
-                    *set_pending_mark = None;
-                    annotate_action.dispatch((id, permanent.content(), permanent.context(), None));
-                    set_data.update(|set_data| {
-                        log::debug!(
-                            "app.rs :: Inserting permanent WordMark into data with ID: {:?}",
-                            id
-                        );
-                        _ = set_data.insert(id, permanent);
-                    });
-                    set_show_ya.update(|d| {
-                        log::debug!("app.rs :: Inserting ID into show_ya: {:?}", id);
-                        if !d.contains(&id) {
-                            d.push(id);
-                        }
-                    });
-                }
-            });
+        if lifecycle.with_untracked(|l| l.pending().is_some()) {
+            dispatch.call(Event::Tick(delta));
         }
     });
 
     let clear_mouse_out_listener = use_event_listener(use_window(), ev::mouseleave, move |_| {
         log::debug!("clear pendning mark on ev::mouseleave");
-        replace_pending.call(None);
+        dispatch.call(Event::Leave);
     });
 
     let clear_win_blur_listener = use_event_listener(use_window(), ev::blur, move |_| {
         log::debug!("clear pendning mark on ev::blur");
-        replace_pending.call(None);
+        dispatch.call(Event::Leave);
     });
 
     on_cleanup(move || {
         clear_mouse_move_listener();
         clear_mouse_out_listener();
         clear_pointer_down_listener();
+        clear_context_menu_listener();
+        clear_simplified_listener();
+        clear_offer_listener();
         clear_pointer_up_listener();
         clear_win_blur_listener();
+        command::stop();
     });
 
     let visible_annotations = create_memo(move |_| {
-        let data = data.get();
+        lifecycle.with(|l| {
+            l.open()
+                .map(|e| (e.trigger.clone(), e.id))
+                .collect::<Vec<_>>()
+        })
+    });
 
-        show_ya
-            .get()
-            .into_iter()
-            .filter_map(|id| data.get(&id).map(|d| (*d, id)))
-            .collect::<Vec<_>>()
+    create_effect(move |_| {
+        let ids = visible_annotations.with(|v| v.iter().map(|(_, id)| *id).collect::<Vec<_>>());
+        let evicted = stack.try_update(|s| s.sync(&ids)).unwrap_or_default();
+
+        for id in evicted {
+            log::debug!("app.rs :: Too many popovers open, closing ID: {:?}", id);
+            dispatch.call(Event::Close(id, None));
+        }
     });
 
     let translate_value = annotate_action.value();
     create_render_effect(move |_| {
         if let Some((id, annotation)) = translate_value.get() {
-            let data = data.get_untracked();
-            if let Some(wd) = data.get(&id) {
-                wd.annotate(Some(annotation));
-            } else {
-                log::error!("no entry for translation id {id}");
-            }
+            dispatch.call(Event::Annotated(id, annotation));
         }
     });
 
     let close_cb = Callback::new(move |(id, quality): (Uuid, Option<bool>)| {
-        set_show_ya.update(|s| {
-            _ = s.retain(|v| v != &id);
-        });
+        dispatch.call(Event::Close(id, quality));
+    });
 
-        if let Some(good) = quality.as_ref() {
-            let data = data.get_untracked();
-            let wd = data.get(&id).unwrap();
-            if !wd.skip_feedback() {
-                let annotation = wd.annotation().unwrap().unwrap();
+    let regenerate_cb = Callback::new(move |id: Uuid| {
+        dispatch.call(Event::Regenerate(id));
+    });
 
-                success_record_action.dispatch((annotation.id, *good));
-                wd.feedback(true);
-            }
-        }
+    let show_cb = Callback::new(move |(id, shown): (Uuid, usize)| {
+        dispatch.call(Event::Show(id, shown));
+    });
 
-        if quality.is_none() || quality == Some(false) {
-            set_data.update(|d| {
-                let an = d.get(&id).unwrap();
-                an.unmount().unwrap();
-                _ = d.remove(&id);
-            });
-        }
+    let expand_cb = Callback::new(move |id: Uuid| {
+        dispatch.call(Event::Expand(id));
     });
 
-    let regenerate_cb = Callback::new(move |id: Uuid| {
-        let data = data.get();
-        let entry = data.get(&id).unwrap();
-        entry.feedback(false);
-        let word = entry.content();
-        let context = entry.context();
-        let annotation = entry.annotation().map(|a| a.ok()).flatten();
-        annotate_action.dispatch((
-            id,
-            word.clone(),
-            context.clone(),
-            annotation.as_ref().map(|a| a.annotation.clone()),
-        ));
-        entry.annotate(None);
-        if let Some(annotation) = annotation {
-            success_record_action.dispatch((annotation.id, false));
-        }
+    let dismiss_cb = Callback::new(move |id: Uuid| {
+        dispatch.call(Event::Dismiss(id));
     });
 
     let mount = use_document()
@@ -268,18 +641,75 @@ pub fn App() -> impl IntoView {
             <style inner_html={ANIMATE_STYLE}/>
             <style inner_html={STYLE}/>
 
-            <div id="ya-ya-extension-root" node_ref=extension_root>
+            <Themed theme id="ya-ya-extension-root" node_ref=extension_root>
+                <SimplifyButton
+                    paragraph=offered
+                    on_simplify=move |paragraph| {
+                        set_offered.set(None);
+                        simplify(paragraph);
+                    }
+                />
+                {move || article.get().map(|article| view! {
+                    <ReaderView article on_close=move |_| set_article.set(None)/>
+                })}
                 <For each=move || visible_annotations.get()
                     key=|wd| wd.1
                     let:word
                 >
-                    {match word.0 {
-                        PermanentTrigger::Word(wd) => view!{
-                            <YaWordPopover word=wd close_cb regenerate_cb/>
-                        }.into_view(),
-                    }}
+                    {
+                        let (trigger, id) = word;
+                        let annotation = create_memo(move |_| {
+                            lifecycle.with(|l| l.entry(&id).and_then(|e| e.state.annotation()))
+                        });
+                        let tier = create_memo(move |_| {
+                            lifecycle.with(|l| l.entry(&id).map(|e| e.tier).unwrap_or(Tier::Full))
+                        });
+                        let version = create_memo(move |_| {
+                            lifecycle.with(|l| {
+                                l.entry(&id)
+                                    .map(|e| (e.shown, e.history.len()))
+                                    .unwrap_or_default()
+                            })
+                        });
+                        let rejected = create_memo(move |_| {
+                            lifecycle.with(|l| {
+                                l.entry(&id)
+                                    .map(|e| {
+                                        e.history[..e.shown]
+                                            .iter()
+                                            .map(|a| a.annotation.clone())
+                                            .collect::<Vec<_>>()
+                                    })
+                                    .unwrap_or_default()
+                            })
+                        });
+                        move || match tier.get() {
+                            Tier::Gloss => view!{
+                                <YaGlossPopover
+                                    trigger=trigger.clone()
+                                    stack
+                                    annotation
+                                    expand_cb
+                                    dismiss_cb
+                                />
+                            }.into_view(),
+                            Tier::Full => view!{
+                                <YaTriggerPopover
+                                    trigger=trigger.clone()
+                                    stack
+                                    annotation
+                                    close_cb
+                                    regenerate_cb
+                                    version
+                                    rejected
+                                    show_cb
+                                    auto_read
+                                />
+                            }.into_view(),
+                        }
+                    }
                 </For>
-            </div>
+            </Themed>
         </Portal>
     }
 }
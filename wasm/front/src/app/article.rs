@@ -0,0 +1,321 @@
+use common::sentences::split_sentences;
+use leptos::{document, window};
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use crate::MOUNT;
+
+/// Blocks of text the article is read from.
+const BLOCK_SELECTOR: &str = "p, li, blockquote, h2, h3, h4, pre";
+
+/// Parts of the page that are never the article, wherever they are.
+const CHROME_SELECTOR: &str =
+    "nav, aside, footer, header, [role=navigation], [role=complementary], [aria-hidden=true]";
+
+/// Class and id words of the containers likely holding the article, see [`class_weight`].
+const POSITIVE: &[&str] = &[
+    "article", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+
+/// Class and id words of the containers likely holding anything else.
+const NEGATIVE: &[&str] = &[
+    "ad-", "banner", "comment", "footer", "menu", "meta", "nav", "promo", "related", "share",
+    "sidebar", "social", "sponsor", "widget",
+];
+
+/// Shorter paragraphs are captions, bylines and buttons rather than text.
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Blocks mostly made of links are lists of other pages.
+const MAX_LINK_DENSITY: f64 = 0.5;
+
+/// Chunks are merged up to this length, in chars.
+const MIN_CHUNK_LENGTH: usize = 400;
+
+/// Chunks are split at sentences past this length, in chars, a single sentence is never split.
+const MAX_CHUNK_LENGTH: usize = 1500;
+
+/// The main text of the page, as the reader view shows it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub title: String,
+    /// Origin of the page, the context of every chunk.
+    pub origin: String,
+    /// Paragraph-sized parts of the text, see [`chunks`].
+    pub chunks: Vec<String>,
+}
+
+/// How much the `class` and `id` of a container say it holds the article.
+pub fn class_weight(class_and_id: &str) -> f64 {
+    let names = class_and_id.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| names.contains(w));
+
+    match (has(POSITIVE), has(NEGATIVE)) {
+        (true, false) => 25.0,
+        (false, true) => -25.0,
+        _ => 0.0,
+    }
+}
+
+/// What a paragraph adds to the score of its container: longer paragraphs
+/// with more clauses are more likely to be the article.
+pub fn paragraph_score(text: &str) -> f64 {
+    let length = text.chars().count();
+    if length < MIN_PARAGRAPH_LENGTH {
+        return 0.0;
+    }
+    let commas = text.chars().filter(|c| matches!(c, ',' | '，')).count();
+
+    1.0 + commas as f64 + (length as f64 / 100.0).min(3.0)
+}
+
+/// Splits the `paragraphs` into chunks of about a paragraph: short ones are joined
+/// with the next, long ones are split at sentences.
+pub fn chunks<S: AsRef<str>>(paragraphs: &[S]) -> Vec<String> {
+    let pieces = paragraphs
+        .iter()
+        .map(|p| p.as_ref().trim())
+        .filter(|p| !p.is_empty())
+        .flat_map(split_long);
+
+    let mut chunks = vec![];
+    let mut current = String::new();
+    for piece in pieces {
+        let length = current.chars().count();
+        if length > 0
+            && (length >= MIN_CHUNK_LENGTH || length + piece.chars().count() > MAX_CHUNK_LENGTH)
+        {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+fn split_long(paragraph: &str) -> Vec<String> {
+    if paragraph.chars().count() <= MAX_CHUNK_LENGTH {
+        return vec![paragraph.to_string()];
+    }
+
+    split_sentences(paragraph)
+        .into_iter()
+        .fold(Vec::<String>::new(), |mut acc, sentence| {
+            match acc.last_mut().filter(|last| {
+                last.chars().count() + 1 + sentence.chars().count() <= MAX_CHUNK_LENGTH
+            }) {
+                Some(last) => {
+                    last.push(' ');
+                    last.push_str(sentence);
+                }
+                None => acc.push(sentence.to_string()),
+            }
+            acc
+        })
+}
+
+fn elements(root: &Element, selector: &str) -> Vec<Element> {
+    let Ok(list) = root.query_selector_all(selector) else {
+        return vec![];
+    };
+
+    (0..list.length())
+        .filter_map(|n| list.get(n))
+        .filter_map(|node| node.dyn_into::<Element>().ok())
+        .collect()
+}
+
+fn text_of(element: &Element) -> String {
+    element
+        .text_content()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn link_density(element: &Element, text: &str) -> f64 {
+    let length = text.chars().count();
+    if length == 0 {
+        return 0.0;
+    }
+    let linked = elements(element, "a")
+        .iter()
+        .map(|a| text_of(a).chars().count())
+        .sum::<usize>();
+
+    linked as f64 / length as f64
+}
+
+fn is_chrome(element: &Element) -> bool {
+    element
+        .closest(format!("#{MOUNT}, {CHROME_SELECTOR}").as_str())
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+/// The container with the best score: paragraphs add their score to their parent
+/// and half of it to their grandparent, like Readability does.
+fn article_root(body: &Element) -> Option<Element> {
+    let mut scores = Vec::<(Element, f64)>::new();
+    let mut add = |element: Element, score: f64| match scores
+        .iter_mut()
+        .find(|(e, _)| e.is_same_node(Some(&element)))
+    {
+        Some((_, total)) => *total += score,
+        None => {
+            let names = format!(
+                "{} {}",
+                element.get_attribute("class").unwrap_or_default(),
+                element.id()
+            );
+            let base = match element.tag_name().to_uppercase().as_str() {
+                "ARTICLE" | "MAIN" => 10.0,
+                "DIV" | "SECTION" => 5.0,
+                _ => 0.0,
+            };
+            scores.push((element, base + class_weight(&names) + score));
+        }
+    };
+
+    for paragraph in elements(body, "p, pre") {
+        if is_chrome(&paragraph) {
+            continue;
+        }
+        let score = paragraph_score(&text_of(&paragraph));
+        if score == 0.0 {
+            continue;
+        }
+        let Some(parent) = paragraph.parent_element() else {
+            continue;
+        };
+        if let Some(grandparent) = parent.parent_element() {
+            add(grandparent, score / 2.0);
+        }
+        add(parent, score);
+    }
+
+    scores
+        .into_iter()
+        .map(|(element, score)| {
+            let density = link_density(&element, &text_of(&element));
+            (element, score * (1.0 - density))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(element, _)| element)
+}
+
+/// Paragraphs of the article in `root`, the innermost blocks only.
+fn paragraphs(root: &Element) -> Vec<String> {
+    elements(root, BLOCK_SELECTOR)
+        .into_iter()
+        .filter(|block| {
+            !is_chrome(block)
+                && block
+                    .query_selector(BLOCK_SELECTOR)
+                    .ok()
+                    .flatten()
+                    .is_none()
+        })
+        .filter_map(|block| {
+            let text = text_of(&block);
+            let is_heading = matches!(block.tag_name().to_uppercase().as_str(), "H2" | "H3" | "H4");
+            let long_enough = is_heading || text.chars().count() >= MIN_PARAGRAPH_LENGTH;
+
+            (long_enough && link_density(&block, &text) < MAX_LINK_DENSITY).then_some(text)
+        })
+        .collect()
+}
+
+/// The main text of the page, `None` when there is nothing to read.
+pub fn extract_article() -> Option<Article> {
+    let body = document().body()?;
+    let root = article_root(&body)?;
+    let chunks = chunks(&paragraphs(&root));
+    if chunks.is_empty() {
+        return None;
+    }
+
+    let title = elements(&body, "h1")
+        .first()
+        .map(text_of)
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| document().title());
+
+    Some(Article {
+        title,
+        origin: window().location().origin().unwrap_or_default(),
+        chunks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighs_class_names() {
+        assert_eq!(class_weight("post-content article__body"), 25.0);
+        assert_eq!(class_weight("sidebar widget"), -25.0);
+        assert_eq!(class_weight("comment-content"), 0.0);
+        assert_eq!(class_weight("wrapper"), 0.0);
+    }
+
+    #[test]
+    fn scores_paragraphs() {
+        assert_eq!(paragraph_score("Читать далее"), 0.0);
+
+        let short = "Синхротрон — большой прибор в форме кольца.";
+        let long = "Синхротрон — большой прибор в форме кольца, в нём разгоняют частицы, \
+                    а потом изучают, как они светятся.";
+        assert!(paragraph_score(long) > paragraph_score(short));
+        // Length counts up to 300 chars.
+        assert_eq!(paragraph_score(&"синхротрон ".repeat(100)), 4.0);
+    }
+
+    #[test]
+    fn joins_short_paragraphs() {
+        let chunks = chunks(&["Заголовок", "", "Первый абзац.", "Второй абзац."]);
+
+        assert_eq!(
+            chunks,
+            vec!["Заголовок\n\nПервый абзац.\n\nВторой абзац.".to_string()]
+        );
+    }
+
+    #[test]
+    fn keeps_long_paragraphs_apart() {
+        let paragraph = "Частицы разгоняют по кругу. ".repeat(20);
+        let chunks = chunks(&[paragraph.trim(), paragraph.trim()]);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], paragraph.trim());
+    }
+
+    #[test]
+    fn splits_too_long_paragraphs_at_sentences() {
+        let sentence = "Частицы разгоняют по кругу почти до скорости света. ";
+        let paragraph = sentence.repeat(100);
+        let chunks = chunks(&[paragraph]);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= MAX_CHUNK_LENGTH);
+            assert!(chunk.ends_with("света."));
+        }
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| c.matches("света.").count())
+                .sum::<usize>(),
+            100
+        );
+    }
+}
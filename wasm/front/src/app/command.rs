@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+
+use common::message::ExplainTarget;
+use leptos::{Callable, Callback};
+use uuid::Uuid;
+
+/// What the popup, the side panel and the background ask of the page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    ScanDifficultWords,
+    OpenReader,
+    Explain(ExplainTarget),
+    /// Scrolls to the trigger with the id and flashes it.
+    Reveal(Uuid),
+}
+
+thread_local! {
+    // Handed over in the module rather than as window events, which any page script could dispatch.
+    static RECEIVER: RefCell<Option<Callback<Command>>> = RefCell::default();
+}
+
+/// Hands `command` to the app, dropped while the app is not mounted.
+pub fn run(command: Command) {
+    match RECEIVER.with_borrow(|r| *r) {
+        Some(receiver) => receiver.call(command),
+        None => log::warn!("command.rs :: No app to run {command:?}"),
+    }
+}
+
+/// Makes `receiver` run every command of [`run`], until [`stop`].
+pub fn listen(receiver: Callback<Command>) {
+    RECEIVER.set(Some(receiver));
+}
+
+pub fn stop() {
+    RECEIVER.set(None);
+}
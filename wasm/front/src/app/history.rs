@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+
+use common::message::Explanation;
+
+use super::{
+    lifecycle::{Entry, TriggerState},
+    mark::PermanentTrigger,
+};
+
+/// Every explanation asked for on the page, oldest first, kept after their marks are removed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History(Vec<Explanation>);
+
+impl History {
+    /// Updates the history with the `current` triggers, returns whether anything changed.
+    pub fn sync(&mut self, current: Vec<Explanation>) -> bool {
+        let before = self.0.clone();
+
+        for known in self.0.iter_mut() {
+            known.on_page = false;
+        }
+        for explanation in current {
+            match self.0.iter_mut().find(|e| e.id == explanation.id) {
+                Some(known) => {
+                    // Another answer is on its way, the last one stays until it comes.
+                    let answer = explanation.answer.or(known.answer.take());
+                    *known = Explanation {
+                        answer,
+                        ..explanation
+                    };
+                }
+                None => self.0.push(explanation),
+            }
+        }
+
+        self.0 != before
+    }
+
+    pub fn explanations(&self) -> &[Explanation] {
+        &self.0
+    }
+}
+
+thread_local! {
+    static HISTORY: RefCell<History> = RefCell::default();
+}
+
+/// The history of the page, for the side panel.
+pub fn explanations() -> Vec<Explanation> {
+    HISTORY.with_borrow(|h| h.explanations().to_vec())
+}
+
+/// Updates the history of the page, returns the explanations when they changed.
+pub fn sync(current: Vec<Explanation>) -> Option<Vec<Explanation>> {
+    HISTORY.with_borrow_mut(|h| h.sync(current).then(|| h.explanations().to_vec()))
+}
+
+pub fn explanation(entry: &Entry<PermanentTrigger>) -> Explanation {
+    Explanation {
+        id: entry.id.to_string(),
+        kind: entry.trigger.kind(),
+        content: entry.trigger.content(),
+        context: entry.trigger.context(),
+        answer: entry.history.get(entry.shown).map(|a| a.annotation.clone()),
+        rating: match entry.state {
+            TriggerState::Rated(_, good) => Some(good),
+            TriggerState::Annotating | TriggerState::Annotated(_) => None,
+        },
+        on_page: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::annotation::RequestKind;
+
+    use super::*;
+
+    fn explanation(id: &str, answer: Option<&str>) -> Explanation {
+        Explanation {
+            id: id.to_string(),
+            kind: RequestKind::Word,
+            content: "синхротрон".to_string(),
+            context: "в синхротроне разгоняют частицы".to_string(),
+            answer: answer.map(str::to_string),
+            rating: None,
+            on_page: true,
+        }
+    }
+
+    #[test]
+    fn keeps_removed_explanations() {
+        let mut history = History::default();
+
+        assert!(history.sync(vec![explanation("a", None), explanation("b", None)]));
+        assert!(!history.sync(vec![explanation("a", None), explanation("b", None)]));
+        assert!(history.sync(vec![explanation("b", Some("прибор"))]));
+
+        let ids = history
+            .explanations()
+            .iter()
+            .map(|e| (e.id.as_str(), e.on_page))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![("a", false), ("b", true)]);
+    }
+
+    #[test]
+    fn keeps_the_answer_while_regenerating() {
+        let mut history = History::default();
+        history.sync(vec![explanation("a", Some("прибор"))]);
+
+        assert!(!history.sync(vec![explanation("a", None)]));
+        assert_eq!(history.explanations()[0].answer.as_deref(), Some("прибор"));
+
+        assert!(history.sync(vec![explanation("a", Some("кольцо"))]));
+        assert_eq!(history.explanations()[0].answer.as_deref(), Some("кольцо"));
+    }
+}
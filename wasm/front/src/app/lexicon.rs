@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const FREQUENCY_LIST: &str = include_str!("./ru_freq.txt");
+
+/// Inflectional endings tried by [`stems`].
+const ENDINGS: &[&str] = &[
+    "ями", "ами", "ого", "его", "ому", "ему", "ыми", "ими", "ешь", "ете", "ишь", "ите", "ить",
+    "ать", "ять", "еть", "ых", "их", "ой", "ей", "ий", "ый", "ая", "яя", "ое", "ее", "ую", "юю",
+    "ом", "ем", "ам", "ям", "ах", "ях", "ов", "ев", "ет", "ит", "ут", "ют", "ат", "ят", "ла", "ло",
+    "ли", "ть", "ти", "а", "я", "о", "е", "ы", "и", "у", "ю", "ь", "й",
+];
+
+const REFLEXIVE: &[&str] = &["ся", "сь"];
+
+/// Shortest stem, "дом" of "дома".
+const MIN_STEM_LEN: usize = 3;
+
+/// Shorter stems only match a word of the list as is: "ста" is a stem of both "стать" and "стая".
+const MIN_SHARED_STEM_LEN: usize = 4;
+
+/// Rank lookup over the bundled Russian frequency list.
+pub struct Lexicon {
+    forms: HashMap<String, usize>,
+    stems: HashMap<String, usize>,
+}
+
+impl Lexicon {
+    pub fn bundled() -> &'static Self {
+        static LEXICON: OnceLock<Lexicon> = OnceLock::new();
+        LEXICON.get_or_init(|| Self::parse(FREQUENCY_LIST))
+    }
+
+    pub fn parse(list: &str) -> Self {
+        let mut forms = HashMap::new();
+        let mut stems = HashMap::<String, usize>::new();
+
+        // Repeated forms keep their first rank and do not push the next ones down.
+        let mut rank = 0;
+        for form in list.lines().map(normalize).filter(|f| !f.is_empty()) {
+            if forms.contains_key(&form) {
+                continue;
+            }
+            rank += 1;
+
+            for stem in self::stems(&form)
+                .into_iter()
+                .filter(|s| s.chars().count() >= MIN_SHARED_STEM_LEN)
+            {
+                _ = stems.entry(stem).or_insert(rank);
+            }
+            _ = forms.insert(form, rank);
+        }
+
+        Self { forms, stems }
+    }
+
+    /// Number of ranked forms in the list.
+    pub fn len(&self) -> usize {
+        self.forms.len()
+    }
+
+    /// 1-based rank of the word, matching the exact form first and any of its stems second.
+    pub fn rank(&self, word: &str) -> Option<usize> {
+        let form = normalize(word);
+
+        self.forms.get(&form).copied().or_else(|| {
+            self::stems(&form)
+                .iter()
+                .filter_map(|s| {
+                    if s.chars().count() >= MIN_SHARED_STEM_LEN {
+                        self.stems.get(s)
+                    } else {
+                        self.forms.get(s)
+                    }
+                })
+                .min()
+                .copied()
+        })
+    }
+
+    /// Whether hovering the word is not worth an explanation.
+    pub fn is_trivial(&self, word: &str, max_rank: usize) -> bool {
+        let word = word.trim();
+
+        word.chars().count() < 2
+            || !word.chars().any(|c| c.is_alphabetic())
+            || word.chars().any(|c| c.is_numeric())
+            || self.rank(word).map(|r| r <= max_rank).unwrap_or_default()
+    }
+}
+
+pub fn normalize(word: &str) -> String {
+    word.trim().to_lowercase().replace('ё', "е")
+}
+
+/// Lemmatizer-lite: every stem left after dropping a reflexive suffix and one
+/// inflectional ending, keeping at least [`MIN_STEM_LEN`] characters.
+///
+/// Inflected forms of a word usually share one of them, which is all [`Lexicon`] needs.
+pub fn stems(word: &str) -> Vec<String> {
+    let form = normalize(word);
+    let base = REFLEXIVE
+        .iter()
+        .find_map(|s| form.strip_suffix(s))
+        .filter(|rest| rest.chars().count() >= MIN_STEM_LEN)
+        .unwrap_or(&form);
+
+    let mut stems = vec![base.to_string()];
+    stems.extend(
+        ENDINGS
+            .iter()
+            .filter_map(|s| base.strip_suffix(s))
+            .filter(|rest| rest.chars().count() >= MIN_STEM_LEN)
+            .map(String::from),
+    );
+
+    stems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranks_in_order() {
+        let lexicon = Lexicon::parse("и\n\nГод\n  стать \nгод\n");
+
+        assert_eq!(lexicon.len(), 3);
+        assert_eq!(lexicon.rank("и"), Some(1));
+        assert_eq!(lexicon.rank("год"), Some(2));
+        assert_eq!(lexicon.rank("стать"), Some(3));
+        assert_eq!(lexicon.rank("синхротрон"), None);
+    }
+
+    #[test]
+    fn repeated_forms_keep_ranks_dense() {
+        let lexicon = Lexicon::parse("и\nещё\nЕще\nгод\nещё\nстать");
+
+        assert_eq!(lexicon.len(), 4);
+        assert_eq!(lexicon.rank("еще"), Some(2));
+        assert_eq!(lexicon.rank("год"), Some(3));
+        assert_eq!(lexicon.rank("стать"), Some(4));
+    }
+
+    #[test]
+    fn stems_drop_one_ending() {
+        assert!(stems("говоришься").contains(&"говор".to_string()));
+        assert!(stems("Дома").contains(&"дом".to_string()));
+        assert!(stems("ёлками").contains(&"елк".to_string()));
+        assert!(stems("стая")
+            .iter()
+            .all(|s| s.chars().count() >= MIN_STEM_LEN));
+    }
+
+    #[test]
+    fn trivial_words() {
+        let lexicon = Lexicon::bundled();
+        let max_rank = 500;
+
+        let table = [
+            // Single letters, numbers and punctuation.
+            ("я", true),
+            ("В", true),
+            ("42", true),
+            ("2024-й", true),
+            ("—", true),
+            ("...", true),
+            // Common words as is and inflected.
+            ("год", true),
+            ("Года", true),
+            ("человеку", true),
+            ("домами", true),
+            ("говорит", true),
+            ("сказала", true),
+            ("стать", true),
+            // Short stems do not make unrelated words common.
+            ("стая", false),
+            ("стаи", false),
+            // Rare words, and common ones past the rank.
+            ("синхротрон", false),
+            ("статья", false),
+        ];
+
+        for (word, trivial) in table {
+            assert_eq!(lexicon.is_trivial(word, max_rank), trivial, "{word}");
+        }
+        assert!(lexicon.is_trivial("статья", 600));
+    }
+}
@@ -0,0 +1,595 @@
+use common::{annotation::Annotation, error::YaYaError, settings::Reopen};
+use uuid::Uuid;
+
+/// State of a permanent trigger.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerState {
+    /// Waiting for the annotation request.
+    Annotating,
+    /// The annotation or the error is shown.
+    Annotated(Result<Annotation, YaYaError>),
+    /// The reader rated the shown annotation, further ratings of it are ignored.
+    Rated(Annotation, bool),
+}
+
+impl TriggerState {
+    pub fn annotation(&self) -> Option<Result<Annotation, YaYaError>> {
+        match self {
+            Self::Annotating => None,
+            Self::Annotated(res) => Some(res.clone()),
+            Self::Rated(annotation, _) => Some(Ok(annotation.clone())),
+        }
+    }
+}
+
+/// How much of the annotation an open popover shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// A one-line gloss, expands on click.
+    Gloss,
+    /// The full explanation with rating buttons.
+    Full,
+}
+
+impl From<Reopen> for Tier {
+    fn from(reopen: Reopen) -> Self {
+        match reopen {
+            Reopen::Gloss => Tier::Gloss,
+            Reopen::Full => Tier::Full,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry<T> {
+    pub id: Uuid,
+    pub trigger: T,
+    pub state: TriggerState,
+    pub open: bool,
+    pub tier: Tier,
+    /// Every annotation received for the trigger, oldest first.
+    pub history: Vec<Annotation>,
+    /// Index of the shown annotation in `history`.
+    pub shown: usize,
+    /// Ids of the rated annotations, with their rating.
+    pub ratings: Vec<(usize, bool)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pending<P> {
+    mark: P,
+    elapsed: f64,
+    /// Whether [`Effect::Start`] was emitted, a first tick may not move the clock.
+    started: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<P, T> {
+    /// The caret rests on a new mark.
+    Hover(P),
+    /// The caret left the pending mark, or the window lost focus.
+    Leave,
+    /// Animation frame, ms since the previous one.
+    Tick(f64),
+    /// Make the mark permanent without waiting for the timer.
+    Activate(P),
+    /// Make the pending mark permanent without waiting for the timer.
+    Commit,
+    /// The mark from [`Effect::Promote`] is now permanent.
+    Promoted(Uuid, T),
+    /// The annotation request for the trigger finished.
+    Annotated(Uuid, Result<Annotation, YaYaError>),
+    /// The caret rests on an existing trigger, open it in the given tier.
+    Reopen(Uuid, Tier),
+    /// The reader asked for the full explanation.
+    Expand(Uuid),
+    /// The pointer left a gloss.
+    Dismiss(Uuid),
+    /// The popover was closed, with the reader's rating if any.
+    Close(Uuid, Option<bool>),
+    /// The reader asked for another annotation.
+    Regenerate(Uuid),
+    /// The reader switched to an earlier or later annotation in the history.
+    Show(Uuid, usize),
+}
+
+/// Side effects the reducer asks the caller to run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect<P, T> {
+    /// The pending mark started its timer.
+    Start,
+    /// Restore the page under a pending mark.
+    Unmount(P),
+    /// Turn the mark into a trigger, answering with [`Event::Promoted`].
+    Promote(P),
+    /// Request an annotation, different from every `rejected` one.
+    Annotate { id: Uuid, rejected: Vec<String> },
+    /// Record the reader's rating of an annotation of the trigger.
+    Record {
+        trigger: T,
+        annotation: usize,
+        good: bool,
+    },
+    /// Restore the page under a trigger that is gone.
+    Remove(T),
+}
+
+/// Lifecycle of marks: hover, pending, permanent, annotated, rated and closed.
+///
+/// There is at most one pending mark, and every permanent trigger is owned here.
+/// The reducer is pure, the caller runs the returned [`Effect`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lifecycle<P, T> {
+    trigger_after: f64,
+    pending: Option<Pending<P>>,
+    entries: Vec<Entry<T>>,
+}
+
+impl<P, T: Clone> Lifecycle<P, T> {
+    pub fn new(trigger_after: f64) -> Self {
+        Self {
+            trigger_after,
+            pending: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Applies to the marks that start pending from now on.
+    pub fn set_trigger_after(&mut self, trigger_after: f64) {
+        self.trigger_after = trigger_after;
+    }
+
+    pub fn pending(&self) -> Option<&P> {
+        self.pending.as_ref().map(|p| &p.mark)
+    }
+
+    pub fn entry(&self, id: &Uuid) -> Option<&Entry<T>> {
+        self.entries.iter().find(|e| &e.id == id)
+    }
+
+    pub fn find(&self, f: impl Fn(&T) -> bool) -> Option<Uuid> {
+        self.entries.iter().find(|e| f(&e.trigger)).map(|e| e.id)
+    }
+
+    /// Every trigger, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry<T>> {
+        self.entries.iter()
+    }
+
+    /// Triggers with an open popover, oldest first.
+    pub fn open(&self) -> impl Iterator<Item = &Entry<T>> {
+        self.entries.iter().filter(|e| e.open)
+    }
+
+    pub fn reduce(&mut self, event: Event<P, T>) -> Vec<Effect<P, T>> {
+        match event {
+            Event::Hover(mark) => {
+                self.hide_glosses(None);
+                let effects = self.take_pending();
+                self.pending = Some(Pending {
+                    mark,
+                    elapsed: 0.0,
+                    started: false,
+                });
+                effects
+            }
+            Event::Leave => self.take_pending(),
+            Event::Tick(delta) => {
+                let mut effects = vec![];
+                if let Some(pending) = self.pending.as_mut() {
+                    if !pending.started {
+                        pending.started = true;
+                        effects.push(Effect::Start);
+                    }
+                    pending.elapsed += delta;
+                    if pending.elapsed >= self.trigger_after {
+                        let pending = self.pending.take().unwrap();
+                        effects.push(Effect::Promote(pending.mark));
+                    }
+                }
+                effects
+            }
+            Event::Activate(mark) => {
+                let mut effects = self.take_pending();
+                effects.push(Effect::Promote(mark));
+                effects
+            }
+            Event::Commit => self
+                .pending
+                .take()
+                .map(|p| vec![Effect::Promote(p.mark)])
+                .unwrap_or_default(),
+            Event::Promoted(id, trigger) => {
+                self.entries.push(Entry {
+                    id,
+                    trigger,
+                    state: TriggerState::Annotating,
+                    open: true,
+                    tier: Tier::Full,
+                    history: vec![],
+                    shown: 0,
+                    ratings: vec![],
+                });
+                vec![Effect::Annotate {
+                    id,
+                    rejected: vec![],
+                }]
+            }
+            Event::Annotated(id, res) => {
+                if let Some(entry) = self
+                    .entry_mut(&id)
+                    .filter(|e| e.state == TriggerState::Annotating)
+                {
+                    if let Ok(annotation) = &res {
+                        entry.history.push(annotation.clone());
+                        entry.shown = entry.history.len() - 1;
+                    }
+                    entry.state = TriggerState::Annotated(res);
+                }
+                vec![]
+            }
+            Event::Reopen(id, tier) => {
+                self.hide_glosses(Some(id));
+                if let Some(entry) = self.entry_mut(&id).filter(|e| !e.open) {
+                    entry.open = true;
+                    entry.tier = tier;
+                }
+                vec![]
+            }
+            Event::Expand(id) => {
+                if let Some(entry) = self.entry_mut(&id) {
+                    entry.tier = Tier::Full;
+                }
+                vec![]
+            }
+            Event::Dismiss(id) => {
+                if let Some(entry) = self.entry_mut(&id).filter(|e| e.tier == Tier::Gloss) {
+                    entry.open = false;
+                }
+                vec![]
+            }
+            Event::Close(id, quality) => self.close(id, quality),
+            Event::Regenerate(id) => {
+                let entry = if let Some(entry) = self.entry_mut(&id) {
+                    entry
+                } else {
+                    return vec![];
+                };
+
+                let mut effects = vec![];
+                match &entry.state {
+                    TriggerState::Annotating => return effects,
+                    TriggerState::Annotated(Ok(annotation)) => {
+                        effects.push(Effect::Record {
+                            trigger: entry.trigger.clone(),
+                            annotation: annotation.id,
+                            good: false,
+                        });
+                        entry.ratings.push((annotation.id, false));
+                    }
+                    TriggerState::Annotated(Err(_)) | TriggerState::Rated(_, _) => {}
+                }
+                entry.state = TriggerState::Annotating;
+
+                let rejected = entry.history.iter().map(|a| a.annotation.clone()).collect();
+                effects.push(Effect::Annotate { id, rejected });
+                effects
+            }
+            Event::Show(id, index) => {
+                if let Some(entry) = self
+                    .entry_mut(&id)
+                    .filter(|e| e.state != TriggerState::Annotating)
+                {
+                    if let Some(annotation) = entry.history.get(index).cloned() {
+                        let rating = entry.ratings.iter().find(|(id, _)| *id == annotation.id);
+                        entry.state = match rating {
+                            Some(&(_, good)) => TriggerState::Rated(annotation, good),
+                            None => TriggerState::Annotated(Ok(annotation)),
+                        };
+                        entry.shown = index;
+                    }
+                }
+                vec![]
+            }
+        }
+    }
+
+    fn close(&mut self, id: Uuid, quality: Option<bool>) -> Vec<Effect<P, T>> {
+        let mut effects = vec![];
+        let at = if let Some(at) = self.entries.iter().position(|e| e.id == id) {
+            at
+        } else {
+            return effects;
+        };
+
+        let entry = &mut self.entries[at];
+        entry.open = false;
+
+        if let (Some(good), TriggerState::Annotated(Ok(annotation))) = (quality, &entry.state) {
+            effects.push(Effect::Record {
+                trigger: entry.trigger.clone(),
+                annotation: annotation.id,
+                good,
+            });
+            entry.ratings.push((annotation.id, good));
+            entry.state = TriggerState::Rated(annotation.clone(), good);
+        }
+
+        if quality != Some(true) {
+            let entry = self.entries.remove(at);
+            effects.push(Effect::Remove(entry.trigger));
+        }
+
+        effects
+    }
+
+    fn hide_glosses(&mut self, except: Option<Uuid>) {
+        for entry in self.entries.iter_mut() {
+            if entry.tier == Tier::Gloss && Some(entry.id) != except {
+                entry.open = false;
+            }
+        }
+    }
+
+    fn take_pending(&mut self) -> Vec<Effect<P, T>> {
+        self.pending
+            .take()
+            .map(|p| vec![Effect::Unmount(p.mark)])
+            .unwrap_or_default()
+    }
+
+    fn entry_mut(&mut self, id: &Uuid) -> Option<&mut Entry<T>> {
+        self.entries.iter_mut().find(|e| &e.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Machine = Lifecycle<&'static str, &'static str>;
+
+    const TIMER: f64 = 100.0;
+
+    fn annotation(id: usize) -> Annotation {
+        Annotation {
+            annotation: format!("annotation {id}"),
+            id,
+        }
+    }
+
+    fn promoted(machine: &mut Machine) -> Uuid {
+        let id = Uuid::new_v4();
+        _ = machine.reduce(Event::Promoted(id, "trigger"));
+        id
+    }
+
+    #[test]
+    fn keeps_one_pending_mark() {
+        let mut machine = Machine::new(TIMER);
+
+        assert!(machine.reduce(Event::Hover("a")).is_empty());
+        assert_eq!(
+            machine.reduce(Event::Hover("b")),
+            vec![Effect::Unmount("a")]
+        );
+        assert_eq!(machine.pending(), Some(&"b"));
+        assert_eq!(machine.reduce(Event::Leave), vec![Effect::Unmount("b")]);
+        assert_eq!(machine.pending(), None);
+    }
+
+    #[test]
+    fn promotes_after_timer() {
+        let mut machine = Machine::new(TIMER);
+        _ = machine.reduce(Event::Hover("a"));
+
+        assert_eq!(machine.reduce(Event::Tick(60.0)), vec![Effect::Start]);
+        assert_eq!(
+            machine.reduce(Event::Tick(60.0)),
+            vec![Effect::Promote("a")]
+        );
+        assert_eq!(machine.pending(), None);
+        assert!(machine.reduce(Event::Tick(60.0)).is_empty());
+    }
+
+    #[test]
+    fn starts_once_with_zero_ticks() {
+        let mut machine = Machine::new(TIMER);
+        _ = machine.reduce(Event::Hover("a"));
+
+        assert_eq!(machine.reduce(Event::Tick(0.0)), vec![Effect::Start]);
+        assert!(machine.reduce(Event::Tick(0.0)).is_empty());
+        assert!(machine.reduce(Event::Tick(60.0)).is_empty());
+    }
+
+    #[test]
+    fn activate_skips_timer() {
+        let mut machine = Machine::new(TIMER);
+        _ = machine.reduce(Event::Hover("a"));
+
+        assert_eq!(
+            machine.reduce(Event::Activate("b")),
+            vec![Effect::Unmount("a"), Effect::Promote("b")]
+        );
+    }
+
+    #[test]
+    fn commit_promotes_pending_mark() {
+        let mut machine = Machine::new(TIMER);
+        assert!(machine.reduce(Event::Commit).is_empty());
+
+        _ = machine.reduce(Event::Hover("a"));
+        assert_eq!(machine.reduce(Event::Commit), vec![Effect::Promote("a")]);
+        assert_eq!(machine.pending(), None);
+        assert!(machine.reduce(Event::Tick(TIMER)).is_empty());
+    }
+
+    #[test]
+    fn rating_good_keeps_trigger() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(7))));
+
+        assert_eq!(
+            machine.reduce(Event::Close(id, Some(true))),
+            vec![Effect::Record {
+                trigger: "trigger",
+                annotation: 7,
+                good: true
+            }]
+        );
+        assert_eq!(machine.open().count(), 0);
+
+        _ = machine.reduce(Event::Reopen(id, Tier::Full));
+        assert!(machine.reduce(Event::Close(id, Some(true))).is_empty());
+        assert!(machine.entry(&id).is_some());
+    }
+
+    #[test]
+    fn reopened_gloss_expands_and_hides() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(7))));
+        _ = machine.reduce(Event::Close(id, Some(true)));
+
+        _ = machine.reduce(Event::Reopen(id, Tier::Gloss));
+        assert_eq!(machine.entry(&id).unwrap().tier, Tier::Gloss);
+        _ = machine.reduce(Event::Hover("a"));
+        assert_eq!(machine.open().count(), 0);
+
+        _ = machine.reduce(Event::Reopen(id, Tier::Gloss));
+        _ = machine.reduce(Event::Expand(id));
+        _ = machine.reduce(Event::Dismiss(id));
+        assert_eq!(machine.entry(&id).unwrap().tier, Tier::Full);
+        assert_eq!(machine.open().count(), 1);
+    }
+
+    #[test]
+    fn closing_error_removes_without_record() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Err(YaYaError::ServerError)));
+
+        assert_eq!(
+            machine.reduce(Event::Close(id, Some(false))),
+            vec![Effect::Remove("trigger")]
+        );
+        assert!(machine.entry(&id).is_none());
+        assert!(machine.reduce(Event::Close(id, None)).is_empty());
+    }
+
+    #[test]
+    fn regenerate_records_and_rewords() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(3))));
+
+        assert_eq!(
+            machine.reduce(Event::Regenerate(id)),
+            vec![
+                Effect::Record {
+                    trigger: "trigger",
+                    annotation: 3,
+                    good: false
+                },
+                Effect::Annotate {
+                    id,
+                    rejected: vec!["annotation 3".to_string()]
+                }
+            ]
+        );
+        assert_eq!(machine.entry(&id).unwrap().state, TriggerState::Annotating);
+        assert!(machine.reduce(Event::Regenerate(id)).is_empty());
+    }
+
+    #[test]
+    fn history_keeps_versions_and_their_ratings() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(1))));
+        _ = machine.reduce(Event::Regenerate(id));
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(2))));
+
+        let effects = machine.reduce(Event::Regenerate(id));
+        assert_eq!(
+            effects.last(),
+            Some(&Effect::Annotate {
+                id,
+                rejected: vec!["annotation 1".to_string(), "annotation 2".to_string()]
+            })
+        );
+        assert!(machine.reduce(Event::Show(id, 0)).is_empty());
+        assert_eq!(machine.entry(&id).unwrap().shown, 1);
+
+        _ = machine.reduce(Event::Annotated(id, Err(YaYaError::ServerError)));
+        _ = machine.reduce(Event::Show(id, 0));
+        assert_eq!(machine.entry(&id).unwrap().shown, 0);
+        assert_eq!(
+            machine.entry(&id).unwrap().state,
+            TriggerState::Rated(annotation(1), false)
+        );
+    }
+
+    #[test]
+    fn rated_versions_stay_rated() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(1))));
+        _ = machine.reduce(Event::Regenerate(id));
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(2))));
+        _ = machine.reduce(Event::Close(id, Some(true)));
+
+        _ = machine.reduce(Event::Show(id, 1));
+        assert_eq!(
+            machine.entry(&id).unwrap().state,
+            TriggerState::Rated(annotation(2), true)
+        );
+        assert!(machine.reduce(Event::Close(id, Some(true))).is_empty());
+
+        _ = machine.reduce(Event::Show(id, 0));
+        _ = machine.reduce(Event::Show(id, 1));
+        assert!(machine.reduce(Event::Close(id, Some(true))).is_empty());
+        assert_eq!(
+            machine.entry(&id).unwrap().ratings,
+            vec![(1, false), (2, true)]
+        );
+    }
+
+    #[test]
+    fn replaced_versions_stay_rejected() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(1))));
+        _ = machine.reduce(Event::Regenerate(id));
+        _ = machine.reduce(Event::Annotated(id, Ok(annotation(2))));
+
+        _ = machine.reduce(Event::Show(id, 0));
+        assert_eq!(
+            machine.entry(&id).unwrap().state,
+            TriggerState::Rated(annotation(1), false)
+        );
+        assert!(machine.reduce(Event::Close(id, Some(true))).is_empty());
+
+        _ = machine.reduce(Event::Show(id, 0));
+        assert_eq!(
+            machine.reduce(Event::Regenerate(id)),
+            vec![Effect::Annotate {
+                id,
+                rejected: vec!["annotation 1".to_string(), "annotation 2".to_string()]
+            }]
+        );
+        assert_eq!(machine.entry(&id).unwrap().ratings, vec![(1, false)]);
+    }
+
+    #[test]
+    fn late_annotation_is_ignored() {
+        let mut machine = Machine::new(TIMER);
+        let id = promoted(&mut machine);
+        _ = machine.reduce(Event::Close(id, None));
+
+        assert!(machine
+            .reduce(Event::Annotated(id, Ok(annotation(1))))
+            .is_empty());
+        assert!(machine.entry(&id).is_none());
+    }
+}
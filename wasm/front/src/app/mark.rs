@@ -1,87 +1,129 @@
-use common::{annotation::Annotation, error::YaYaError};
-use leptos::*;
+use std::{fmt::Debug, ops::Deref, rc::Rc};
+
+use common::{
+    annotation::{success_record, Annotation, AnnotationRequest, RequestKind},
+    error::YaYaError,
+};
+use futures::future::{FutureExt, LocalBoxFuture};
 use uuid::Uuid;
 use wasm_bindgen::JsValue;
+use web_sys::{Element, Node};
 
-use super::word::{WordMark, WordPermanentTrigger};
+/// A mounted mark the reader can open an annotation for.
+///
+/// New kinds of triggers implement this and plug into `App` and `YaTriggerPopover` as is.
+pub trait Trigger: Debug {
+    fn id(&self) -> Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum PermanentTrigger {
-    Word(RwSignal<WordPermanentTrigger>),
-}
+    fn kind(&self) -> RequestKind;
 
-impl PermanentTrigger {
-    pub fn unmount(&self) -> Result<(), wasm_bindgen::JsValue> {
-        match self {
-            Self::Word(wd) => wd.get_untracked().unmount(),
-        }
-    }
+    /// The text to explain.
+    fn content(&self) -> String;
+
+    fn context(&self) -> String;
+
+    /// Elements the popover is placed against: before the mark, the mark, after the mark.
+    fn anchors(&self) -> (Element, Element, Element);
+
+    fn contains(&self, node: &Node) -> bool;
+
+    /// Restores the page as it was before the trigger was mounted.
+    fn unmount(&self) -> Result<(), JsValue>;
 
-    pub fn content(&self) -> String {
-        match self {
-            Self::Word(wd) => wd.get_untracked().word(),
+    fn request(&self) -> AnnotationRequest {
+        AnnotationRequest {
+            kind: self.kind(),
+            content: self.content(),
+            context: self.context(),
         }
     }
 
-    pub fn context(&self) -> String {
-        match self {
-            Self::Word(wd) => wd.get_untracked().context(),
-        }
+    fn annotate(
+        &self,
+        rejected: Vec<String>,
+    ) -> LocalBoxFuture<'static, Result<Annotation, YaYaError>> {
+        self.request().send(rejected).boxed_local()
     }
 
-    pub fn annotate(&self, value: Option<Result<Annotation, YaYaError>>) {
-        match self {
-            Self::Word(wd) => wd.update(|wd| wd.annotation = value),
-        }
+    fn feedback(
+        &self,
+        annotation: usize,
+        good: bool,
+    ) -> LocalBoxFuture<'static, Result<(), YaYaError>> {
+        success_record(annotation, good).boxed_local()
     }
+}
+
+/// A mark waiting for the dwell timer before it becomes a [`Trigger`].
+pub trait PendingTrigger: Debug {
+    fn kind(&self) -> RequestKind;
+
+    fn content(&self) -> String;
+
+    fn context(&self) -> String;
+
+    fn start_animation(&self) -> Result<(), JsValue>;
+
+    fn make_permanent(&self, id: Uuid) -> Result<PermanentTrigger, JsValue>;
+
+    fn is_same(&self, node: Node, pos: u32) -> bool;
 
-    pub fn annotation(&self) -> Option<Result<Annotation, YaYaError>> {
-        match self {
-            Self::Word(wd) => wd.get_untracked().annotation.clone(),
+    /// Whether `node` is inside the mark, wherever in it.
+    fn contains(&self, node: &Node) -> bool;
+
+    fn unmount(&self) -> Result<(), JsValue>;
+
+    fn request(&self) -> AnnotationRequest {
+        AnnotationRequest {
+            kind: self.kind(),
+            content: self.content(),
+            context: self.context(),
         }
     }
+}
 
-    pub fn feedback(&self, val: bool) {
-        match self {
-            Self::Word(wd) => wd.update_untracked(|wd| wd.feedback = val),
-        }
+#[derive(Debug, Clone)]
+pub struct PermanentTrigger(Rc<dyn Trigger>);
+
+impl PermanentTrigger {
+    pub fn new(trigger: impl Trigger + 'static) -> Self {
+        Self(Rc::new(trigger))
     }
+}
 
-    pub fn skip_feedback(&self) -> bool {
-        match self {
-            Self::Word(wd) => wd.get_untracked().feedback,
-        }
+impl Deref for PermanentTrigger {
+    type Target = dyn Trigger;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum PendingMark {
-    Word(WordMark),
+impl PartialEq for PermanentTrigger {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
 }
 
+#[derive(Debug, Clone)]
+pub struct PendingMark(Rc<dyn PendingTrigger>);
+
 impl PendingMark {
-    pub fn unmount(&self) -> Result<(), wasm_bindgen::JsValue> {
-        match self {
-            Self::Word(wd) => wd.unmount(),
-        }
+    pub fn new(mark: impl PendingTrigger + 'static) -> Self {
+        Self(Rc::new(mark))
     }
+}
 
-    pub fn tick_timer(&mut self, delta: f64) -> bool {
-        match self {
-            Self::Word(wd) => wd.tick_timer(delta),
-        }
-    }
+impl Deref for PendingMark {
+    type Target = dyn PendingTrigger;
 
-    pub fn make_permanent(&self, id: Uuid) -> Result<PermanentTrigger, JsValue> {
-        match self {
-            Self::Word(wd) => WordPermanentTrigger::make_permanent(&wd, id)
-                .map(|d| PermanentTrigger::Word(RwSignal::new(d))),
-        }
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
     }
+}
 
-    pub fn is_same(&self, node: web_sys::Node, pos: u32) -> bool {
-        match self {
-            Self::Word(wd) => wd.is_same(node, pos),
-        }
+impl PartialEq for PendingMark {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
     }
 }
@@ -0,0 +1,211 @@
+use common::annotation::{Annotation, AnnotationRequest, RequestKind};
+use common::error::YaYaError;
+
+use super::lexicon::stems;
+
+/// How many explanations deep a lookup can go below the trigger's own.
+pub const MAX_LOOKUP_DEPTH: usize = 3;
+
+/// A word looked up from inside an explanation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookup {
+    pub word: String,
+    /// The sentence of the parent explanation the word was found in.
+    pub context: String,
+    pub annotation: Option<Result<Annotation, YaYaError>>,
+    /// Earlier answers the reader did not understand, oldest first.
+    pub rejected: Vec<String>,
+}
+
+impl Lookup {
+    pub fn request(&self) -> AnnotationRequest {
+        AnnotationRequest {
+            kind: RequestKind::Word,
+            content: self.word.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+/// What opening a lookup did to the trail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// A new lookup at this depth, waiting for its annotation.
+    Opened(usize),
+    /// The word was already on the trail, so the trail went back to it.
+    Back(usize),
+    /// The trail is as deep as it may go.
+    TooDeep,
+}
+
+/// Breadcrumbs from the trigger's word down to the shown lookup.
+///
+/// Depth 0 is the trigger itself, lookups start at depth 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trail {
+    root: String,
+    lookups: Vec<Lookup>,
+}
+
+impl Trail {
+    pub fn new(root: String) -> Self {
+        Self {
+            root,
+            lookups: Vec::new(),
+        }
+    }
+
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    pub fn lookups(&self) -> &[Lookup] {
+        &self.lookups
+    }
+
+    /// Depth of the shown explanation.
+    pub fn depth(&self) -> usize {
+        self.lookups.len()
+    }
+
+    pub fn lookup(&self, depth: usize) -> Option<&Lookup> {
+        depth.checked_sub(1).and_then(|at| self.lookups.get(at))
+    }
+
+    /// Looks up `word` from the shown explanation.
+    pub fn open(&mut self, word: String, context: String) -> Step {
+        if is_same_word(&self.root, &word) {
+            self.back(0);
+            return Step::Back(0);
+        }
+        if let Some(at) = self
+            .lookups
+            .iter()
+            .position(|l| is_same_word(&l.word, &word))
+        {
+            self.back(at + 1);
+            return Step::Back(at + 1);
+        }
+        if self.depth() >= MAX_LOOKUP_DEPTH {
+            return Step::TooDeep;
+        }
+
+        self.lookups.push(Lookup {
+            word,
+            context,
+            annotation: None,
+            rejected: vec![],
+        });
+        Step::Opened(self.depth())
+    }
+
+    /// Goes back up to the explanation at `depth`.
+    pub fn back(&mut self, depth: usize) {
+        self.lookups.truncate(depth);
+    }
+
+    /// Stores the annotation of the lookup at `depth`, unless the trail moved on from `word`.
+    pub fn annotated(
+        &mut self,
+        depth: usize,
+        word: &str,
+        annotation: Result<Annotation, YaYaError>,
+    ) -> bool {
+        match self.lookup_mut(depth).filter(|l| l.word == word) {
+            Some(lookup) => {
+                lookup.annotation = Some(annotation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drops the annotation at `depth` to ask again, rejecting it when there was one.
+    ///
+    /// Returns what to send: the lookup and every rejected answer.
+    pub fn regenerate(&mut self, depth: usize) -> Option<(Lookup, Vec<String>)> {
+        let lookup = self.lookup_mut(depth)?;
+        if let Some(Ok(annotation)) = lookup.annotation.take() {
+            lookup.rejected.push(annotation.annotation);
+        }
+
+        Some((lookup.clone(), lookup.rejected.clone()))
+    }
+
+    fn lookup_mut(&mut self, depth: usize) -> Option<&mut Lookup> {
+        depth.checked_sub(1).and_then(|at| self.lookups.get_mut(at))
+    }
+}
+
+/// Whether two words are forms of one word, so looking one up from the other would go in circles.
+fn is_same_word(a: &str, b: &str) -> bool {
+    let b = stems(b);
+    stems(a).iter().any(|s| b.contains(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trail() -> Trail {
+        Trail::new("излучение".to_string())
+    }
+
+    fn open(trail: &mut Trail, word: &str) -> Step {
+        trail.open(word.to_string(), format!("Контекст для {word}."))
+    }
+
+    #[test]
+    fn opens_lookups_in_order() {
+        let mut trail = trail();
+        assert_eq!(open(&mut trail, "синхротрон"), Step::Opened(1));
+        assert_eq!(open(&mut trail, "электрон"), Step::Opened(2));
+        assert_eq!(trail.lookup(2).map(|l| l.word.as_str()), Some("электрон"));
+        assert_eq!(trail.lookup(1).map(|l| l.word.as_str()), Some("синхротрон"));
+        assert_eq!(trail.lookup(0), None);
+    }
+
+    #[test]
+    fn cycles_go_back() {
+        let mut trail = trail();
+        _ = open(&mut trail, "синхротрон");
+        _ = open(&mut trail, "электрон");
+
+        assert_eq!(open(&mut trail, "синхротрона"), Step::Back(1));
+        assert_eq!(trail.depth(), 1);
+
+        assert_eq!(open(&mut trail, "Излучения"), Step::Back(0));
+        assert_eq!(trail.depth(), 0);
+    }
+
+    #[test]
+    fn depth_is_bounded() {
+        let mut trail = trail();
+        for (depth, word) in ["синхротрон", "электрон", "частица"].iter().enumerate()
+        {
+            assert_eq!(open(&mut trail, word), Step::Opened(depth + 1));
+        }
+
+        assert_eq!(open(&mut trail, "энергия"), Step::TooDeep);
+        assert_eq!(trail.depth(), MAX_LOOKUP_DEPTH);
+    }
+
+    #[test]
+    fn stale_annotations_are_dropped() {
+        let mut trail = trail();
+        _ = open(&mut trail, "синхротрон");
+        trail.back(0);
+        _ = open(&mut trail, "электрон");
+
+        let annotation = Annotation {
+            annotation: "Ответ".to_string(),
+            id: 1,
+        };
+        assert!(!trail.annotated(1, "синхротрон", Ok(annotation.clone())));
+        assert!(trail.annotated(1, "электрон", Ok(annotation)));
+
+        let (lookup, rejected) = trail.regenerate(1).unwrap();
+        assert_eq!(lookup.annotation, None);
+        assert_eq!(rejected, vec!["Ответ".to_string()]);
+    }
+}
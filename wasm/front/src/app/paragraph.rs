@@ -0,0 +1,243 @@
+use common::{
+    annotation::{Annotation, AnnotationRequest, RequestKind},
+    error::YaYaError,
+    sanitize::render_annotation,
+};
+use leptos::*;
+use leptos_use::{
+    use_event_listener, use_event_listener_with_options, use_window, UseEventListenerOptions,
+};
+use uuid::Uuid;
+use wasm_bindgen::JsValue;
+use web_sys::{DocumentFragment, Element};
+
+use super::{MARK_ROOT_ATTRIBUTE, SIMPLIFIED_ATTRIBUTE, SIMPLIFIED_TOGGLE_ATTRIBUTE};
+
+/// Blocks of text that can be simplified in place.
+pub const PARAGRAPH_SELECTOR: &str = "p, li, blockquote, dd, figcaption";
+
+/// Shorter paragraphs get no [`SimplifyButton`], they are captions and labels rather than text.
+const MIN_OFFERED_LENGTH: usize = 80;
+
+/// Width of the [`SimplifyButton`] kept inside the viewport, px.
+const BUTTON_WIDTH: f64 = 96.0;
+
+/// A paragraph whose content is replaced with its plain-language version.
+///
+/// Like [`super::word::WordMark`], everything added lives under one root element,
+/// and the original nodes are kept aside as they were, so unmounting restores the page exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphMark {
+    pub id: Uuid,
+    pub paragraph: Element,
+    pub content: String,
+    /// The children of the paragraph, moved out untouched.
+    original: DocumentFragment,
+    root: Element,
+    /// Holds the simplified text, under the "упрощено" toggle.
+    simplified: Element,
+}
+
+impl ParagraphMark {
+    /// The simplified paragraph `element` is in, if any.
+    fn simplified_root(element: &Element) -> Option<Element> {
+        element
+            .closest(format!("[{SIMPLIFIED_ATTRIBUTE}]").as_str())
+            .ok()
+            .flatten()
+    }
+
+    fn content_of(paragraph: &Element) -> String {
+        paragraph
+            .text_content()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether `paragraph` is neither simplified nor editable.
+    fn can_mount(paragraph: &Element) -> bool {
+        let nested = paragraph
+            .query_selector(format!("[{SIMPLIFIED_ATTRIBUTE}]").as_str())
+            .ok()
+            .flatten();
+        let editable = paragraph.closest("[contenteditable]").ok().flatten();
+
+        Self::simplified_root(paragraph).is_none() && nested.is_none() && editable.is_none()
+    }
+
+    /// The paragraph `element` is in, if it is long enough to offer its simplification.
+    pub fn offered(element: &Element) -> Option<Element> {
+        element
+            .closest(PARAGRAPH_SELECTOR)
+            .ok()
+            .flatten()
+            .filter(|p| Self::content_of(p).chars().count() >= MIN_OFFERED_LENGTH)
+            .filter(Self::can_mount)
+    }
+
+    /// Moves the content of `paragraph` aside, showing that it is being simplified.
+    pub fn mount(paragraph: &Element) -> Option<Self> {
+        if !Self::can_mount(paragraph) {
+            log::debug!("paragraph.rs :: Paragraph is simplified or editable, returning None");
+            return None;
+        }
+
+        let content = Self::content_of(paragraph);
+        if content.is_empty() {
+            log::debug!("paragraph.rs :: Paragraph is empty, returning None");
+            return None;
+        }
+
+        let id = Uuid::new_v4();
+
+        log::debug!("paragraph.rs :: Creating the root of the simplified paragraph");
+        // The simplified text comes in paragraphs of its own.
+        let root = document().create_element("div").ok()?;
+        root.set_attribute(MARK_ROOT_ATTRIBUTE, "").ok()?;
+        root.set_attribute(SIMPLIFIED_ATTRIBUTE, "loading").ok()?;
+
+        let toggle = document().create_element("button").ok()?;
+        toggle.set_attribute("type", "button").ok()?;
+        toggle
+            .set_attribute(SIMPLIFIED_TOGGLE_ATTRIBUTE, id.to_string().as_str())
+            .ok()?;
+        toggle
+            .set_attribute("title", "Вернуть исходный текст")
+            .ok()?;
+        toggle
+            .set_attribute("aria-label", "Упрощено. Вернуть исходный текст")
+            .ok()?;
+        toggle.set_text_content(Some("упрощено"));
+
+        let simplified = document().create_element("div").ok()?;
+        simplified.set_attribute("role", "status").ok()?;
+        simplified.set_text_content(Some("Упрощаем…"));
+
+        root.append_child(&toggle).ok()?;
+        root.append_child(&simplified).ok()?;
+
+        paragraph
+            .insert_before(&root, paragraph.first_child().as_ref())
+            .ok()?;
+
+        log::debug!("paragraph.rs :: Moving the original content of the paragraph aside");
+        let original = document().create_document_fragment();
+        while let Some(child) = root.next_sibling() {
+            if let Err(err) = original.append_child(&child) {
+                log::error!("paragraph.rs :: Failed to move the paragraph content: {err:?}");
+                // The moved nodes come back in order, before the ones never moved.
+                _ = root.replace_with_with_node_1(&original);
+                return None;
+            }
+        }
+
+        Some(Self {
+            id,
+            paragraph: paragraph.clone(),
+            content,
+            original,
+            root,
+            simplified,
+        })
+    }
+
+    /// Asks for the plain-language version, in the context of the page origin.
+    pub fn request(&self) -> AnnotationRequest {
+        AnnotationRequest {
+            kind: RequestKind::Text,
+            content: self.content.clone(),
+            context: window().location().origin().unwrap_or_default(),
+        }
+    }
+
+    /// Shows the simplified text, or why there is none.
+    pub fn show(&self, answer: &Result<Annotation, YaYaError>) -> Result<(), JsValue> {
+        match answer {
+            Ok(annotation) => {
+                self.root.set_attribute(SIMPLIFIED_ATTRIBUTE, "ready")?;
+                self.simplified
+                    .set_inner_html(&render_annotation(&annotation.annotation));
+            }
+            Err(err) => {
+                log::error!("paragraph.rs :: Failed to simplify the paragraph: {err:?}");
+                self.root.set_attribute(SIMPLIFIED_ATTRIBUTE, "error")?;
+                self.simplified
+                    .set_text_content(Some("Не удалось упростить абзац."));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Puts the original content back in place of the simplified one.
+    pub fn unmount(&self) -> Result<(), JsValue> {
+        if self.root.parent_node().is_none() {
+            // The page rewrote the paragraph, the original would come back twice.
+            return Err(JsValue::from_str("simplified root is detached"));
+        }
+
+        log::debug!("paragraph.rs :: Replacing the root element with the original content");
+        self.root.replace_with_with_node_1(&self.original)?;
+
+        Ok(())
+    }
+}
+
+/// Button next to the `paragraph` under the mouse, simplifies it in place.
+#[component]
+pub fn SimplifyButton(
+    #[prop(into)] paragraph: Signal<Option<Element>>,
+    #[prop(into)] on_simplify: Callback<Element>,
+) -> impl IntoView {
+    // Scrolling of any container moves the paragraph, scroll does not bubble so listen on capture.
+    let (layout, set_layout) = create_signal(0_usize);
+    let clear_scroll_listener = use_event_listener_with_options(
+        use_window(),
+        ev::scroll,
+        move |_| set_layout.update(|l| *l += 1),
+        UseEventListenerOptions::default()
+            .capture(true)
+            .passive(true),
+    );
+    let clear_resize_listener = use_event_listener(use_window(), ev::resize, move |_| {
+        set_layout.update(|l| *l += 1)
+    });
+
+    on_cleanup(move || {
+        clear_scroll_listener();
+        clear_resize_listener();
+    });
+
+    move || {
+        paragraph.get().map(|paragraph| {
+            let style = {
+                let paragraph = paragraph.clone();
+                move || {
+                    layout.track();
+                    let rect = paragraph.get_bounding_client_rect();
+                    let viewport = document()
+                        .document_element()
+                        .map(|r| r.client_width() as f64)
+                        .unwrap_or_default();
+                    // Right against the paragraph, the pointer reaches it without leaving it.
+                    let left = rect.right().min(viewport - BUTTON_WIDTH);
+                    format!("top: {}px; left: {}px;", rect.top(), left.max(0.0))
+                }
+            };
+
+            view! {
+                <button
+                    class="ya-ya-simplify-button"
+                    style=style
+                    title="Упростить абзац"
+                    aria-label="Упростить абзац"
+                    on:click=move |_| on_simplify.call(paragraph.clone())
+                >
+                    "упростить"
+                </button>
+            }
+        })
+    }
+}
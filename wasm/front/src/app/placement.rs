@@ -0,0 +1,544 @@
+/// Smallest distance between the arrow and a corner of the popover, px.
+pub const ARROW_INSET: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+
+    /// The overlapping part of both rects, empty rects keep a zero size.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+
+        Rect::new(
+            x,
+            y,
+            (self.right().min(other.right()) - x).max(0.0),
+            (self.bottom().min(other.bottom()) - y).max(0.0),
+        )
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+}
+
+/// Edge of the popover the callout points from.
+///
+/// `Top` places the popover below the mark, `Left` to the right of it and so on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalloutSide {
+    Top,
+    Left,
+    Bottom,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl CalloutSide {
+    pub const ALL: [CalloutSide; 8] = [
+        CalloutSide::Top,
+        CalloutSide::Bottom,
+        CalloutSide::Left,
+        CalloutSide::Right,
+        CalloutSide::TopLeft,
+        CalloutSide::TopRight,
+        CalloutSide::BottomLeft,
+        CalloutSide::BottomRight,
+    ];
+
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            CalloutSide::Top => "ya-ya-popover-side-top",
+            CalloutSide::Left => "ya-ya-popover-side-left",
+            CalloutSide::Bottom => "ya-ya-popover-side-bottom",
+            CalloutSide::Right => "ya-ya-popover-side-right",
+            CalloutSide::TopLeft => "ya-ya-popover-side-top-left",
+            CalloutSide::TopRight => "ya-ya-popover-side-top-right",
+            CalloutSide::BottomLeft => "ya-ya-popover-side-bottom-left",
+            CalloutSide::BottomRight => "ya-ya-popover-side-bottom-right",
+        }
+    }
+
+    pub fn animate(&self) -> &'static str {
+        match self {
+            CalloutSide::Top => "animate__fadeInBottom",
+            CalloutSide::Left => "animate__fadeInRight",
+            CalloutSide::Bottom => "animate__fadeInTop",
+            CalloutSide::Right => "animate__fadeInLeft",
+            CalloutSide::TopLeft => "animate__fadeInBottomRight",
+            CalloutSide::TopRight => "animate__fadeInBottomLeft",
+            CalloutSide::BottomLeft => "animate__fadeInTopRight",
+            CalloutSide::BottomRight => "animate__fadeInTopLeft",
+        }
+    }
+
+    /// The side on the opposite edge of the mark.
+    pub fn flip(&self) -> Self {
+        match self {
+            CalloutSide::Top => CalloutSide::Bottom,
+            CalloutSide::Bottom => CalloutSide::Top,
+            CalloutSide::Left => CalloutSide::Right,
+            CalloutSide::Right => CalloutSide::Left,
+            CalloutSide::TopLeft => CalloutSide::BottomLeft,
+            CalloutSide::TopRight => CalloutSide::BottomRight,
+            CalloutSide::BottomLeft => CalloutSide::TopLeft,
+            CalloutSide::BottomRight => CalloutSide::TopRight,
+        }
+    }
+
+    /// Whether the popover is below the mark.
+    fn is_below(&self) -> bool {
+        matches!(
+            self,
+            CalloutSide::Top | CalloutSide::TopLeft | CalloutSide::TopRight
+        )
+    }
+
+    /// Whether the popover is above the mark.
+    fn is_above(&self) -> bool {
+        matches!(
+            self,
+            CalloutSide::Bottom | CalloutSide::BottomLeft | CalloutSide::BottomRight
+        )
+    }
+}
+
+/// Everything the placement depends on, in viewport coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacementInput {
+    /// Anchor at the start of the mark.
+    pub before: Rect,
+    pub mark: Rect,
+    /// Anchor at the end of the mark.
+    pub after: Rect,
+    /// Size the popover would take without constraints.
+    pub popover: (f64, f64),
+    /// Visible area: the viewport minus clipping ancestors and fixed headers.
+    pub boundary: Rect,
+    /// Distance between the mark and the popover, room for the arrow.
+    pub gap: f64,
+    /// Smallest distance between the popover and the boundary.
+    pub padding: f64,
+    /// Other popovers to stay clear of when there is a side for it.
+    pub avoid: Vec<Rect>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub side: CalloutSide,
+    /// The popover box, never larger than `max_width` by `max_height`.
+    pub rect: Rect,
+    pub max_width: f64,
+    pub max_height: f64,
+    /// Offset of the arrow along the popover edge, `None` for corner sides.
+    pub arrow: Option<f64>,
+}
+
+impl PlacementInput {
+    fn space_above(&self) -> f64 {
+        self.mark.y - self.boundary.y - self.padding
+    }
+
+    fn space_below(&self) -> f64 {
+        self.boundary.bottom() - self.padding - self.mark.bottom()
+    }
+
+    fn space_left(&self) -> f64 {
+        self.before.x - self.boundary.x - self.padding
+    }
+
+    fn space_right(&self) -> f64 {
+        self.boundary.right() - self.padding - self.after.right()
+    }
+
+    fn is_multiline(&self) -> bool {
+        self.before.y != self.after.y
+    }
+
+    /// Room for the popover on the given side, width by height.
+    pub fn available(&self, side: CalloutSide) -> (f64, f64) {
+        let full_width = self.boundary.width - self.padding * 2.0;
+        let full_height = self.boundary.height - self.padding * 2.0;
+        let right_of_mark = self.boundary.right() - self.padding - self.after.x;
+        let left_of_mark = self.before.x - self.boundary.x - self.padding;
+
+        let (width, height) = match side {
+            CalloutSide::Top => (full_width, self.space_below() - self.gap),
+            CalloutSide::Bottom => (full_width, self.space_above() - self.gap),
+            CalloutSide::Left => (self.space_right() - self.gap, full_height),
+            CalloutSide::Right => (self.space_left() - self.gap, full_height),
+            CalloutSide::TopLeft => (right_of_mark, self.space_below() - self.gap),
+            CalloutSide::TopRight => (left_of_mark, self.space_below() - self.gap),
+            CalloutSide::BottomLeft => (right_of_mark, self.space_above() - self.gap),
+            CalloutSide::BottomRight => (left_of_mark, self.space_above() - self.gap),
+        };
+
+        (width.max(0.0), height.max(0.0))
+    }
+
+    pub fn fits(&self, side: CalloutSide) -> bool {
+        let (width, height) = self.available(side);
+        self.popover.0 <= width && self.popover.1 <= height
+    }
+
+    /// Fits and does not cover any of the popovers to avoid.
+    pub fn is_clear(&self, side: CalloutSide) -> bool {
+        self.fits(side) && {
+            let rect = place_on(self, side).rect;
+            !self.avoid.iter().any(|r| r.overlaps(&rect))
+        }
+    }
+
+    /// The side picked from the spread of space around the mark, before checking the fit.
+    pub fn preferred(&self) -> CalloutSide {
+        let space_above = self.space_above();
+        let space_below = self.space_below();
+        let space_left = self.space_left();
+        let space_right = self.space_right();
+
+        let prefer_top = space_above > space_below;
+        let prefer_left = space_left > space_right;
+
+        let prefer_sideways = self.boundary.width > self.boundary.height || self.is_multiline();
+        let prefer_corner = if prefer_sideways {
+            (space_above - space_below).abs() > self.boundary.height * 0.5
+        } else {
+            (space_left - space_right).abs() > self.boundary.width * 0.5
+        };
+
+        match (prefer_corner, prefer_sideways, prefer_left, prefer_top) {
+            (true, _, true, true) => CalloutSide::BottomRight,
+            (true, _, false, true) => CalloutSide::BottomLeft,
+            (true, _, true, false) => CalloutSide::TopRight,
+            (true, _, false, false) => CalloutSide::TopLeft,
+            (false, false, _, true) => CalloutSide::Bottom,
+            (false, false, _, false) => CalloutSide::Top,
+            (false, true, true, _) => CalloutSide::Right,
+            (false, true, false, _) => CalloutSide::Left,
+        }
+    }
+}
+
+/// Picks the side: the preferred one, its flip, then any other side, first
+/// looking for one clear of other popovers, then for one that fits,
+/// and the least clipped side when nothing fits.
+pub fn place(input: &PlacementInput) -> Placement {
+    let preferred = input.preferred();
+    let candidates = [preferred, preferred.flip()]
+        .into_iter()
+        .chain(CalloutSide::ALL)
+        .collect::<Vec<_>>();
+
+    let side = candidates
+        .iter()
+        .copied()
+        .find(|side| input.is_clear(*side))
+        .or_else(|| candidates.iter().copied().find(|side| input.fits(*side)))
+        .unwrap_or_else(|| {
+            candidates
+                .iter()
+                .copied()
+                .max_by(|a, b| visible_area(input, *a).total_cmp(&visible_area(input, *b)))
+                .unwrap_or(preferred)
+        });
+
+    place_on(input, side)
+}
+
+fn visible_area(input: &PlacementInput, side: CalloutSide) -> f64 {
+    let (width, height) = input.available(side);
+    width.min(input.popover.0) * height.min(input.popover.1)
+}
+
+/// Geometry of the popover on the given side, shifted along the edge to stay in the boundary.
+pub fn place_on(input: &PlacementInput, side: CalloutSide) -> Placement {
+    let (max_width, max_height) = input.available(side);
+    let width = input.popover.0.min(max_width);
+    let height = input.popover.1.min(max_height);
+
+    let mark = input.mark;
+    let mark_center_x = input.before.x + (input.after.x - input.before.x) / 2.0;
+    let mark_center_y = mark.y + mark.height / 2.0;
+
+    let y = if side.is_below() {
+        mark.bottom() + input.gap
+    } else if side.is_above() {
+        mark.y - input.gap - height
+    } else {
+        clamp(
+            mark_center_y - height / 2.0,
+            input.boundary.y + input.padding,
+            input.boundary.bottom() - input.padding - height,
+        )
+    };
+
+    let x = match side {
+        CalloutSide::Top | CalloutSide::Bottom => clamp(
+            mark_center_x - width / 2.0,
+            input.boundary.x + input.padding,
+            input.boundary.right() - input.padding - width,
+        ),
+        CalloutSide::Left => input.after.right() + input.gap,
+        CalloutSide::Right => input.before.x - input.gap - width,
+        CalloutSide::TopLeft | CalloutSide::BottomLeft => input.after.x,
+        CalloutSide::TopRight | CalloutSide::BottomRight => input.before.x - width,
+    };
+
+    let arrow = match side {
+        CalloutSide::Top | CalloutSide::Bottom => Some(arrow_offset(mark_center_x - x, width)),
+        CalloutSide::Left | CalloutSide::Right => Some(arrow_offset(mark_center_y - y, height)),
+        _ => None,
+    };
+
+    Placement {
+        side,
+        rect: Rect::new(x, y, width, height),
+        max_width,
+        max_height,
+        arrow,
+    }
+}
+
+/// Like `f64::clamp`, but the lower bound wins when the range is empty.
+fn clamp(value: f64, min: f64, max: f64) -> f64 {
+    value.min(max).max(min)
+}
+
+fn arrow_offset(offset: f64, length: f64) -> f64 {
+    if length <= ARROW_INSET * 2.0 {
+        length / 2.0
+    } else {
+        clamp(offset, ARROW_INSET, length - ARROW_INSET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const VIEWPORT: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 1200.0,
+        height: 800.0,
+    };
+
+    fn input_at(x: f64, y: f64, popover: (f64, f64), boundary: Rect) -> PlacementInput {
+        PlacementInput {
+            before: Rect::new(x, y, 0.0, 20.0),
+            mark: Rect::new(x, y, 80.0, 20.0),
+            after: Rect::new(x + 80.0, y, 0.0, 20.0),
+            popover,
+            boundary,
+            gap: 12.0,
+            padding: 8.0,
+            avoid: vec![],
+        }
+    }
+
+    fn side() -> impl Strategy<Value = CalloutSide> {
+        prop::sample::select(CalloutSide::ALL.to_vec())
+    }
+
+    fn input() -> impl Strategy<Value = PlacementInput> {
+        (
+            -200.0..1400.0f64,
+            -200.0..1000.0f64,
+            0.0..1500.0f64,
+            0.0..1000.0f64,
+            0.0..300.0f64,
+            0.0..200.0f64,
+        )
+            .prop_map(|(x, y, w, h, bx, by)| {
+                input_at(
+                    x,
+                    y,
+                    (w, h),
+                    Rect::new(bx, by, VIEWPORT.width - bx, VIEWPORT.height - by),
+                )
+            })
+    }
+
+    /// Marks inside the boundary with popovers that usually fit somewhere.
+    fn visible_input() -> impl Strategy<Value = PlacementInput> {
+        (0.0..1120.0f64, 0.0..780.0f64, 0.0..600.0f64, 0.0..400.0f64)
+            .prop_map(|(x, y, w, h)| input_at(x, y, (w, h), VIEWPORT))
+    }
+
+    proptest! {
+        #[test]
+        fn sizes_are_never_negative(input in input(), side in side()) {
+            let placement = place_on(&input, side);
+
+            prop_assert!(placement.max_width >= 0.0);
+            prop_assert!(placement.max_height >= 0.0);
+            prop_assert!(placement.rect.width <= placement.max_width);
+            prop_assert!(placement.rect.height <= placement.max_height);
+        }
+
+        #[test]
+        fn fitting_side_stays_clear_of_mark_and_inside_boundary(
+            input in visible_input(),
+            side in side(),
+        ) {
+            prop_assume!(input.fits(side));
+            let placement = place_on(&input, side);
+            let inner = input.boundary;
+
+            prop_assert!(!placement.rect.overlaps(&input.mark));
+            prop_assert!(placement.rect.x >= inner.x + input.padding - 1e-6);
+            prop_assert!(placement.rect.y >= inner.y + input.padding - 1e-6);
+            prop_assert!(placement.rect.right() <= inner.right() - input.padding + 1e-6);
+            prop_assert!(placement.rect.bottom() <= inner.bottom() - input.padding + 1e-6);
+        }
+
+        #[test]
+        fn popover_is_on_its_side_of_mark(input in input(), side in side()) {
+            let placement = place_on(&input, side);
+            let rect = placement.rect;
+
+            match side {
+                CalloutSide::Top | CalloutSide::TopLeft | CalloutSide::TopRight => {
+                    prop_assert!(rect.y >= input.mark.bottom())
+                }
+                CalloutSide::Bottom | CalloutSide::BottomLeft | CalloutSide::BottomRight => {
+                    prop_assert!(rect.bottom() <= input.mark.y + 1e-6)
+                }
+                CalloutSide::Left => prop_assert!(rect.x >= input.after.right()),
+                CalloutSide::Right => prop_assert!(rect.right() <= input.before.x + 1e-6),
+            }
+        }
+
+        #[test]
+        fn arrow_points_along_the_edge(input in input(), side in side()) {
+            let placement = place_on(&input, side);
+
+            match (side, placement.arrow) {
+                (CalloutSide::Top | CalloutSide::Bottom, Some(arrow)) => {
+                    prop_assert!(arrow >= 0.0 && arrow <= placement.rect.width)
+                }
+                (CalloutSide::Left | CalloutSide::Right, Some(arrow)) => {
+                    prop_assert!(arrow >= 0.0 && arrow <= placement.rect.height)
+                }
+                (_, arrow) => prop_assert_eq!(arrow, None),
+            }
+        }
+
+        #[test]
+        fn picks_preferred_side_when_it_fits(input in input()) {
+            let preferred = input.preferred();
+            prop_assume!(input.fits(preferred));
+
+            prop_assert_eq!(place(&input).side, preferred);
+        }
+
+        #[test]
+        fn picks_a_fitting_side_when_any_fits(input in input()) {
+            prop_assume!(CalloutSide::ALL.iter().any(|s| input.fits(*s)));
+
+            prop_assert!(input.fits(place(&input).side));
+        }
+
+        #[test]
+        fn avoids_other_popovers_when_a_side_is_clear(
+            input in visible_input(),
+            other in (0.0..1100.0f64, 0.0..700.0f64, 0.0..400.0f64, 0.0..300.0f64),
+        ) {
+            let (x, y, w, h) = other;
+            let input = PlacementInput {
+                avoid: vec![Rect::new(x, y, w, h)],
+                ..input
+            };
+            prop_assume!(CalloutSide::ALL.iter().any(|s| input.is_clear(*s)));
+
+            prop_assert!(!place(&input).rect.overlaps(&input.avoid[0]));
+        }
+    }
+
+    #[test]
+    fn every_side_is_preferred_somewhere() {
+        let wide = VIEWPORT;
+        let tall = Rect::new(0.0, 0.0, 600.0, 900.0);
+        let popover = (200.0, 100.0);
+
+        let cases = [
+            (input_at(260.0, 100.0, popover, tall), CalloutSide::Top),
+            (input_at(260.0, 700.0, popover, tall), CalloutSide::Bottom),
+            (input_at(100.0, 400.0, popover, wide), CalloutSide::Left),
+            (input_at(1000.0, 400.0, popover, wide), CalloutSide::Right),
+            (input_at(100.0, 100.0, popover, wide), CalloutSide::TopLeft),
+            (
+                input_at(1000.0, 100.0, popover, wide),
+                CalloutSide::TopRight,
+            ),
+            (
+                input_at(100.0, 700.0, popover, wide),
+                CalloutSide::BottomLeft,
+            ),
+            (
+                input_at(1000.0, 700.0, popover, wide),
+                CalloutSide::BottomRight,
+            ),
+        ];
+
+        for (input, side) in cases {
+            assert_eq!(input.preferred(), side);
+            assert_eq!(place(&input).side, side);
+        }
+    }
+
+    #[test]
+    fn falls_back_when_preferred_does_not_fit() {
+        let input = input_at(1000.0, 100.0, (400.0, 100.0), VIEWPORT);
+        assert_eq!(input.preferred(), CalloutSide::TopRight);
+
+        let input = input_at(300.0, 100.0, (400.0, 100.0), VIEWPORT);
+        assert_eq!(input.preferred(), CalloutSide::TopLeft);
+        assert!(input.fits(CalloutSide::TopLeft));
+
+        let input = input_at(1000.0, 100.0, (1100.0, 100.0), VIEWPORT);
+        assert!(!input.fits(input.preferred()));
+        assert_eq!(place(&input).side, CalloutSide::Top);
+    }
+
+    #[test]
+    fn tight_space_clamps_to_zero() {
+        let input = input_at(0.0, 0.0, (400.0, 300.0), Rect::new(0.0, 0.0, 90.0, 30.0));
+
+        for side in CalloutSide::ALL {
+            let placement = place_on(&input, side);
+            assert!(placement.max_width >= 0.0 && placement.max_height >= 0.0);
+        }
+    }
+}
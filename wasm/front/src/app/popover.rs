@@ -1,243 +1,573 @@
+use common::theme::use_theme;
 use leptos::*;
 use leptos_use::{
-    use_element_bounding, use_window, use_window_scroll, use_window_size, UseElementBoundingReturn,
-    UseWindowSizeReturn,
+    use_element_bounding, use_event_listener, use_event_listener_with_options, use_window,
+    UseElementBoundingReturn, UseEventListenerOptions,
 };
-use web_sys::Element;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum CalloutSide {
-    Top,
-    Left,
-    Bottom,
-    Right,
-    TopLeft,
-    TopRight,
-    BottomLeft,
-    BottomRight,
+use std::{cell::RefCell, rc::Rc};
+
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, FocusOptions, HtmlElement, Node, ShadowRoot};
+
+use super::{
+    placement::{place, Placement, PlacementInput, Rect},
+    stack::{Anchoring, PopoverStack},
+};
+use crate::MOUNT;
+
+/// Distance between the mark and the popover, leaves room for the arrow.
+const GAP: f64 = 14.0;
+
+/// Smallest distance between the popover and the edges of the visible area.
+const EDGE_PADDING: f64 = 8.0;
+
+/// Width of the docked popovers' content, px.
+const DOCK_WIDTH: f64 = 320.0;
+
+/// Width of the drop area along the right edge that docks a popover, px.
+const DOCK_ZONE_WIDTH: f64 = 96.0;
+
+/// Space between docked popovers, px.
+const DOCK_GAP: f64 = 12.0;
+
+/// Fixed or sticky bars taller than this share of the viewport are not treated as headers.
+const MAX_BAR_SHARE: f64 = 0.33;
+
+/// Elements Tab moves between inside a popover.
+const FOCUSABLE: &str =
+    "button:not([disabled]), a[href], input:not([disabled]), [tabindex]:not([tabindex='-1'])";
+
+fn rect_of(el: &Element) -> Rect {
+    let rect = el.get_bounding_client_rect();
+    Rect::new(rect.x(), rect.y(), rect.width(), rect.height())
 }
 
-impl CalloutSide {
-    fn class_name(&self) -> &'static str {
-        match self {
-            CalloutSide::Top => "ya-ya-popover-side-top",
-            CalloutSide::Left => "ya-ya-popover-side-left",
-            CalloutSide::Bottom => "ya-ya-popover-side-bottom",
-            CalloutSide::Right => "ya-ya-popover-side-right",
-            CalloutSide::TopLeft => "ya-ya-popover-side-top-left",
-            CalloutSide::TopRight => "ya-ya-popover-side-top-right",
-            CalloutSide::BottomLeft => "ya-ya-popover-side-bottom-left",
-            CalloutSide::BottomRight => "ya-ya-popover-side-bottom-right",
+fn computed(el: &Element, property: &str) -> String {
+    window()
+        .get_computed_style(el)
+        .ok()
+        .flatten()
+        .and_then(|s| s.get_property_value(property).ok())
+        .unwrap_or_default()
+}
+
+/// The part of the viewport where the mark is visible: clipped by scrolling or
+/// overflow hidden ancestors, and by fixed or sticky bars at the viewport edges.
+///
+/// Bounding rects are in viewport coordinates and already account for transforms and zoom.
+fn visible_boundary(mark: &Element) -> Rect {
+    let root = document().document_element();
+    let viewport = root
+        .as_ref()
+        .map(|r| Rect::new(0.0, 0.0, r.client_width() as f64, r.client_height() as f64))
+        .unwrap_or_else(|| {
+            Rect::new(
+                0.0,
+                0.0,
+                window()
+                    .inner_width()
+                    .ok()
+                    .and_then(|w| w.as_f64())
+                    .unwrap_or_default(),
+                window()
+                    .inner_height()
+                    .ok()
+                    .and_then(|h| h.as_f64())
+                    .unwrap_or_default(),
+            )
+        });
+
+    let mut boundary = viewport;
+    let mut ancestor = mark.parent_element();
+    while let Some(el) = ancestor {
+        if root.as_ref() == Some(&el) || el.tag_name().eq_ignore_ascii_case("body") {
+            break;
+        }
+        let clips = ["overflow-x", "overflow-y"]
+            .iter()
+            .any(|p| computed(&el, p) != "visible");
+        if clips {
+            boundary = boundary.intersect(&rect_of(&el));
         }
+        ancestor = el.parent_element();
+    }
+
+    let mark_rect = rect_of(mark);
+    let probe_x = (mark_rect.x + mark_rect.width / 2.0)
+        .min(viewport.right() - 1.0)
+        .max(viewport.x);
+    let max_bar = viewport.height * MAX_BAR_SHARE;
+
+    if let Some(bar) = edge_bar(probe_x, boundary.y + 1.0, max_bar) {
+        boundary = boundary.intersect(&Rect::new(
+            boundary.x,
+            bar.bottom(),
+            boundary.width,
+            boundary.bottom() - bar.bottom(),
+        ));
+    }
+
+    if let Some(bar) = edge_bar(probe_x, boundary.bottom() - 1.0, max_bar) {
+        boundary = boundary.intersect(&Rect::new(
+            boundary.x,
+            boundary.y,
+            boundary.width,
+            bar.y - boundary.y,
+        ));
     }
 
-    fn animate(&self) -> &'static str {
-        match self {
-            CalloutSide::Top => "animate__fadeInBottom",
-            CalloutSide::Left => "animate__fadeInRight",
-            CalloutSide::Bottom => "animate__fadeInTop",
-            CalloutSide::Right => "animate__fadeInLeft",
-            CalloutSide::TopLeft => "animate__fadeInBottomRight",
-            CalloutSide::TopRight => "animate__fadeInBottomLeft",
-            CalloutSide::BottomLeft => "animate__fadeInTopRight",
-            CalloutSide::BottomRight => "animate__fadeInTopLeft",
+    boundary
+}
+
+/// A fixed or sticky bar covering the given point, like a site header.
+fn edge_bar(x: f64, y: f64, max_height: f64) -> Option<Rect> {
+    let mut el = document().element_from_point(x as f32, y as f32);
+    while let Some(current) = el {
+        if current.id() == MOUNT {
+            return None;
+        }
+        let position = computed(&current, "position");
+        if position == "fixed" || position == "sticky" {
+            let rect = rect_of(&current);
+            return (rect.height < max_height).then_some(rect);
         }
+        el = current.parent_element();
     }
+    None
+}
+
+fn padding_of(el: &Element) -> f64 {
+    computed(el, "padding-left")
+        .trim_end_matches("px")
+        .parse::<f64>()
+        .unwrap_or_default()
 }
 
-const PADDING_MULTIPLIER: f64 = 3.0;
+/// The focused element, looking inside the shadow root `el` is in.
+fn active_element(el: &Element) -> Option<Element> {
+    el.get_root_node()
+        .dyn_into::<ShadowRoot>()
+        .ok()
+        .and_then(|root| root.active_element())
+        .or_else(|| document().active_element())
+}
+
+/// Whether the reader is typing on the page, focus is not taken away from there.
+fn is_typing() -> bool {
+    document()
+        .active_element()
+        .map(|el| {
+            matches!(el.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                || el
+                    .dyn_ref::<HtmlElement>()
+                    .map(|el| el.is_content_editable())
+                    .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+fn focus_without_scroll(el: &HtmlElement) {
+    let options = FocusOptions::new();
+    options.set_prevent_scroll(true);
+    _ = el.focus_with_options(&options);
+}
+
+/// Focusable elements of the popover in Tab order, leaving out the hidden ones.
+fn focusable_in(popover: &Element) -> Vec<HtmlElement> {
+    let Ok(nodes) = popover.query_selector_all(FOCUSABLE) else {
+        return vec![];
+    };
+
+    (0..nodes.length())
+        .filter_map(|n| nodes.get(n))
+        .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+        .filter(|el| el.closest("[hidden]").ok().flatten().is_none())
+        .collect()
+}
+
+/// Gives focus back to the mark the popover was opened from.
+fn return_focus(mark: &Element) {
+    if !mark.is_connected() {
+        return;
+    }
+    // Marks are plain spans, they take focus only with a tabindex.
+    if !mark.has_attribute("tabindex") {
+        _ = mark.set_attribute("tabindex", "-1");
+    }
+    if let Some(mark) = mark.dyn_ref::<HtmlElement>() {
+        focus_without_scroll(mark);
+    }
+}
+
+/// Area along the right edge of the viewport where dropped popovers dock.
+fn dock_zone() -> Rect {
+    let (width, height) = document()
+        .document_element()
+        .map(|r| (r.client_width() as f64, r.client_height() as f64))
+        .unwrap_or_default();
+
+    Rect::new(width - DOCK_ZONE_WIDTH, 0.0, DOCK_ZONE_WIDTH, height)
+}
 
 #[component]
 pub fn YaYaPopover(
     #[prop(attrs)] attrs: Vec<(&'static str, Attribute)>,
+    id: Uuid,
+    stack: RwSignal<PopoverStack>,
     #[prop(into)] elements: MaybeSignal<(Element, Element, Element)>,
     #[prop(into)] close_cb: Callback<()>,
+    /// Accessible name of the dialog.
+    #[prop(into)]
+    label: MaybeSignal<String>,
+    /// Called on Escape instead of `close_cb`.
+    #[prop(optional, into)]
+    escape_cb: Option<Callback<()>>,
+    /// Leaves out the close, pin and drag controls.
+    #[prop(optional)]
+    compact: bool,
     children: ChildrenFn,
 ) -> impl IntoView {
     let elements = Signal::derive(move || elements.get());
-    let before_el = Signal::derive(move || elements.get().0);
     let mark_el = Signal::derive(move || elements.get().1);
-    let after_el = Signal::derive(move || elements.get().2);
 
     let popover_el = create_node_ref::<html::Div>();
+    let content_el = create_node_ref::<html::Div>();
+
+    let anchoring = create_memo(move |_| stack.with(|s| s.anchoring(&id)));
+    let obstacles = create_memo(move |_| stack.with(|s| s.obstacles(&id)));
+    let z_index = create_memo(move |_| stack.with(|s| s.z_index(&id)));
+    let dock_offset = create_memo(move |_| stack.with(|s| s.dock_offset(&id, DOCK_GAP)));
+
+    // Top-left corner of the popover while it is dragged, in viewport coordinates.
+    let (dragging, set_dragging) = create_signal(Option::<(f64, f64)>::None);
+    let grab = store_value((0.0, 0.0));
 
     let UseElementBoundingReturn {
+        x: mark_x,
         y: mark_y,
+        width: mark_width,
         height: mark_height,
         ..
     } = use_element_bounding(mark_el);
 
     let UseElementBoundingReturn {
-        x: before_x,
-        y: before_y,
+        width: popover_width,
+        height: popover_height,
         ..
-    } = use_element_bounding(before_el);
+    } = use_element_bounding(popover_el);
 
-    let UseElementBoundingReturn {
-        x: after_x,
-        y: after_y,
-        ..
-    } = use_element_bounding(after_el);
+    // Scrolling of any container moves the mark, scroll does not bubble so listen on capture.
+    let (layout, set_layout) = create_signal(0_usize);
+    let clear_scroll_listener = use_event_listener_with_options(
+        use_window(),
+        ev::scroll,
+        move |_| set_layout.update(|l| *l += 1),
+        UseEventListenerOptions::default()
+            .capture(true)
+            .passive(true),
+    );
+    let clear_resize_listener = use_event_listener(use_window(), ev::resize, move |_| {
+        set_layout.update(|l| *l += 1)
+    });
 
-    let is_multiline = Signal::derive(move || before_y.get() != after_y.get());
+    on_cleanup(move || {
+        clear_scroll_listener();
+        clear_resize_listener();
+    });
 
-    let (scroll_x, scroll_y) = use_window_scroll();
+    let placement = create_memo(move |_| {
+        layout.track();
+        mark_x.track();
+        mark_y.track();
+        mark_width.track();
+        mark_height.track();
+        popover_width.track();
+        popover_height.track();
+
+        let (before, mark, after) = elements.get();
+        let popover = popover_el.get()?;
+        let content = content_el.get()?;
+
+        let boundary = visible_boundary(&mark);
+        let mark = rect_of(&mark);
+        if !boundary.overlaps(&mark) {
+            log::debug!("popover.rs :: Mark scrolled out of view, hiding popover");
+            return None;
+        }
 
-    let UseWindowSizeReturn {
-        width: win_width,
-        height: win_height,
-    } = use_window_size();
+        // The content is clipped by the max size, its scroll size is what it would take.
+        let padding = padding_of(&popover);
+        let natural = (
+            content.scroll_width() as f64 + padding * 2.0,
+            content.scroll_height() as f64 + padding * 2.0,
+        );
+
+        let input = PlacementInput {
+            before: rect_of(&before),
+            mark,
+            after: rect_of(&after),
+            popover: natural,
+            boundary,
+            gap: GAP,
+            padding: EDGE_PADDING,
+            avoid: obstacles.get(),
+        };
 
-    let space_above = move || mark_y.get() - scroll_y.get();
-    let space_left_before = move || before_x.get() - scroll_x.get();
+        Some((place(&input), padding))
+    });
 
-    let space_below =
-        move || win_height.get() - (mark_y.get() + mark_height.get()) - scroll_y.get();
-    let space_right_after = move || {
-        win_width.get() - (after_x.get() + after_el.get().client_width() as f64) - scroll_x.get()
-    };
+    // Pinned and docked popovers report their drawn box, others the placed one.
+    create_effect(move |_| {
+        popover_width.track();
+        popover_height.track();
+        dock_offset.track();
 
-    let side = create_memo(move |_| {
-        let space_above = space_above();
-        let space_below = space_below();
-        let space_right_after = space_right_after();
-        let space_left_before = space_left_before();
-        let win_width = win_width.get();
-        let win_height = win_height.get();
+        let rect = match (anchoring.get(), dragging.get()) {
+            (_, Some(_)) => return,
+            (Anchoring::Mark, None) => placement.get().map(|(p, _)| p.rect),
+            _ => popover_el.get_untracked().map(|el| rect_of(&el)),
+        };
+        stack.update(|s| s.placed(&id, rect));
+    });
 
-        let prefer_top = space_above > space_below;
-        let prefer_left = space_left_before > space_right_after;
+    let side = Signal::derive(move || match (anchoring.get(), dragging.get()) {
+        (Anchoring::Mark, None) => placement.get().map(|(p, _)| p.side),
+        _ => None,
+    });
 
-        let prefer_sideways = win_width > win_height || is_multiline.get();
-        let prefer_corner = if prefer_sideways {
-            space_above.max(space_below) - space_below.min(space_above) > win_height * 0.5
-        } else {
-            space_left_before.max(space_right_after) - space_right_after.min(space_left_before)
-                > win_width * 0.5
-        };
+    let pos_style = Signal::derive(move || {
+        let z_index = z_index.get();
 
-        let side = match (prefer_corner, prefer_sideways, prefer_left, prefer_top) {
-            (true, _, true, true) => CalloutSide::BottomRight,
-            (true, _, false, true) => CalloutSide::BottomLeft,
-            (true, _, true, false) => CalloutSide::TopRight,
-            (true, _, false, false) => CalloutSide::TopLeft,
-            (false, false, _, true) => CalloutSide::Bottom,
-            (false, false, _, false) => CalloutSide::Top,
-            (false, true, true, _) => CalloutSide::Right,
-            (false, true, false, _) => CalloutSide::Left,
-        };
+        if let Some((x, y)) = dragging.get() {
+            return format!("position: fixed; top: {y}px; left: {x}px; z-index: {z_index};");
+        }
 
-        side
+        match anchoring.get() {
+            Anchoring::Pinned { x, y } => {
+                format!("position: fixed; top: {y}px; left: {x}px; z-index: {z_index};")
+            }
+            Anchoring::Docked => format!(
+                "position: fixed; top: {}px; right: {EDGE_PADDING}px; z-index: {z_index};",
+                EDGE_PADDING + dock_offset.get()
+            ),
+            Anchoring::Mark => {
+                let (Placement { rect, arrow, .. }, _) = if let Some(placement) = placement.get() {
+                    placement
+                } else {
+                    return "visibility: hidden;".to_string();
+                };
+
+                // The mount sits at the page origin, the placement is in viewport coordinates.
+                let origin = document()
+                    .get_element_by_id(MOUNT)
+                    .map(|m| rect_of(&m))
+                    .unwrap_or_default();
+
+                let arrow = arrow
+                    .map(|a| format!(" --ya-ya-arrow-offset: {a}px;"))
+                    .unwrap_or_default();
+
+                format!(
+                    "top: {}px; left: {}px; z-index: {z_index};{arrow}",
+                    rect.y - origin.y,
+                    rect.x - origin.x
+                )
+            }
+        }
     });
 
-    let padding = move || {
-        let value = popover_el
+    let content_max_width = move || match anchoring.get() {
+        Anchoring::Docked => Some(format!("{DOCK_WIDTH}px")),
+        Anchoring::Pinned { .. } => None,
+        Anchoring::Mark => placement
             .get()
-            .zip(use_window().as_ref())
-            .and_then(|(el, win)| win.get_computed_style(&el).ok().flatten())
-            .and_then(|s| s.get_property_value("padding-left").ok())
-            .and_then(|s| s.trim_end_matches("px").parse::<f64>().ok())
-            .unwrap_or_default();
+            .map(|(p, padding)| format!("{}px", (p.max_width - padding * 2.0).max(0.0))),
+    };
 
-        value
+    let content_max_height = move || match anchoring.get() {
+        Anchoring::Docked | Anchoring::Pinned { .. } => Some("60dvh".to_string()),
+        Anchoring::Mark => placement
+            .get()
+            .map(|(p, padding)| format!("{}px", (p.max_height - padding * 2.0).max(0.0))),
     };
 
-    let content_max_width = move || {
-        let space_right_after = space_right_after();
-        let space_left_before = space_left_before();
-        let win_width = win_width.get();
-        let side = side.get();
-        let padding = padding();
+    let theme = use_theme();
+    let class = Signal::derive(move || {
+        side.get()
+            .map(|side| {
+                format!(
+                    "ya-ya-popover {} {}{}",
+                    side.class_name(),
+                    if theme.get().animates() {
+                        format!("animate__animated {}", side.animate())
+                    } else {
+                        String::new()
+                    },
+                    if compact {
+                        " ya-ya-popover-compact"
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .unwrap_or_else(|| match anchoring.get() {
+                Anchoring::Mark if compact => "ya-ya-popover ya-ya-popover-compact".to_string(),
+                Anchoring::Mark if dragging.get().is_none() => "ya-ya-popover".to_string(),
+                _ => "ya-ya-popover ya-ya-popover-detached".to_string(),
+            })
+    });
 
-        match side {
-            CalloutSide::Top | CalloutSide::Bottom => {
-                format!("{}px", win_width - padding * PADDING_MULTIPLIER)
-            }
-            CalloutSide::TopLeft | CalloutSide::BottomLeft | CalloutSide::Left => {
-                format!("{}px", space_right_after - padding * PADDING_MULTIPLIER)
-            }
-            CalloutSide::TopRight | CalloutSide::BottomRight | CalloutSide::Right => {
-                format!("{}px", space_left_before - padding * PADDING_MULTIPLIER)
-            }
-        }
+    let is_detached = move || anchoring.get() != Anchoring::Mark;
+
+    let on_close = move |_| {
+        close_cb.call(());
     };
 
-    let content_max_height = move || {
-        let space_above = space_above();
-        let space_below = space_below();
-        let win_height = win_height.get();
-        let side = side.get();
-        let padding = padding();
+    let on_raise = move |_: ev::PointerEvent| {
+        stack.update(|s| s.raise(&id));
+    };
 
-        match side {
-            CalloutSide::Left | CalloutSide::Right => {
-                format!("{}px", win_height - padding * PADDING_MULTIPLIER)
-            }
-            CalloutSide::Top | CalloutSide::TopLeft | CalloutSide::TopRight => {
-                format!("{}px", space_below - padding * PADDING_MULTIPLIER)
-            }
-            CalloutSide::Bottom | CalloutSide::BottomLeft | CalloutSide::BottomRight => {
-                format!("{}px", space_above - padding * PADDING_MULTIPLIER)
+    // The content takes focus once it is drawn, unless the reader is typing somewhere.
+    let focused_on_open = store_value(false);
+    create_effect(move |_| {
+        let Some(content) = content_el.get() else {
+            return;
+        };
+        if compact || focused_on_open.get_value() || is_typing() {
+            return;
+        }
+        focused_on_open.set_value(true);
+        focus_without_scroll(&content);
+    });
+
+    // Where focus goes back to when the popover closes while focused.
+    let return_to = Rc::new(RefCell::new(Option::<Element>::None));
+
+    let on_focus_in = {
+        let return_to = return_to.clone();
+        move |_| {
+            stack.update(|s| s.raise(&id));
+            *return_to.borrow_mut() = Some(mark_el.get_untracked());
+        }
+    };
+
+    let on_focus_out = {
+        let return_to = return_to.clone();
+        move |ev: ev::FocusEvent| {
+            let inside = ev
+                .related_target()
+                .and_then(|t| t.dyn_into::<Node>().ok())
+                .zip(popover_el.get_untracked())
+                .map(|(target, popover)| popover.contains(Some(&target)))
+                .unwrap_or_default();
+            if !inside {
+                *return_to.borrow_mut() = None;
             }
         }
     };
 
-    let UseElementBoundingReturn {
-        width: popover_width,
-        height: popover_height,
-        ..
-    } = use_element_bounding(popover_el);
+    on_cleanup(move || {
+        if let Some(mark) = return_to.borrow_mut().take() {
+            log::debug!("popover.rs :: Returning focus to the mark");
+            return_focus(&mark);
+        }
+    });
 
-    let pos_style = create_memo(move |_| {
-        let side = side.get();
+    let escape_cb = escape_cb.unwrap_or(close_cb);
 
-        let mark_y = mark_y.get();
-        let mark_height = mark_height.get();
-        let popover_height = popover_height.get();
-        let scroll_y = scroll_y.get();
+    let on_keydown = move |ev: ev::KeyboardEvent| match ev.key().as_str() {
+        "Escape" => {
+            ev.prevent_default();
+            ev.stop_propagation();
+            escape_cb.call(());
+        }
+        "Tab" if !compact => {
+            let Some(popover) = popover_el.get_untracked() else {
+                return;
+            };
+            let focusable = focusable_in(&popover);
+            let (Some(first), Some(last)) = (focusable.first(), focusable.last()) else {
+                ev.prevent_default();
+                return;
+            };
+
+            let active = active_element(&popover);
+            let wrap_to = if ev.shift_key() && active.as_ref() == Some(first.as_ref()) {
+                Some(last)
+            } else if !ev.shift_key() && active.as_ref() == Some(last.as_ref()) {
+                Some(first)
+            } else {
+                None
+            };
+
+            if let Some(el) = wrap_to {
+                ev.prevent_default();
+                focus_without_scroll(el);
+            }
+        }
+        _ => {}
+    };
 
-        let after_x = after_x.get();
-        let before_x = before_x.get();
-        let popover_width = popover_width.get();
-        let scroll_x = scroll_x.get();
+    let label_id = format!("ya-ya-popover-label-{id}");
+    let content_id = format!("ya-ya-popover-content-{id}");
 
-        let top = match side {
-            CalloutSide::Top | CalloutSide::TopLeft | CalloutSide::TopRight => {
-                mark_y + mark_height + scroll_y
-            }
-            CalloutSide::Bottom | CalloutSide::BottomLeft | CalloutSide::BottomRight => {
-                mark_y - popover_height + scroll_y
-            }
-            CalloutSide::Left | CalloutSide::Right => {
-                mark_y + mark_height / 2.0 + scroll_y - popover_height / 2.0
+    let on_pin = move |_| {
+        let anchoring = if is_detached() {
+            Anchoring::Mark
+        } else {
+            let rect = popover_el
+                .get_untracked()
+                .map(|el| rect_of(&el))
+                .unwrap_or_default();
+            Anchoring::Pinned {
+                x: rect.x,
+                y: rect.y,
             }
         };
+        stack.update(|s| s.set_anchoring(&id, anchoring));
+    };
 
-        let left = match side {
-            CalloutSide::Left | CalloutSide::TopLeft | CalloutSide::BottomLeft => {
-                after_x + scroll_x
-            }
-            CalloutSide::Right | CalloutSide::TopRight | CalloutSide::BottomRight => {
-                before_x - popover_width + scroll_x
-            }
-            CalloutSide::Top | CalloutSide::Bottom => {
-                before_x + (after_x - before_x) / 2.0 - popover_width / 2.0 + scroll_x
-            }
+    let on_drag_start = move |ev: ev::PointerEvent| {
+        let popover = if let Some(el) = popover_el.get_untracked() {
+            el
+        } else {
+            return;
         };
+        ev.prevent_default();
 
-        format!("top: {}px; left: {}px;", top, left)
-    });
+        if let Some(handle) = ev
+            .current_target()
+            .and_then(|t| t.dyn_into::<Element>().ok())
+        {
+            _ = handle.set_pointer_capture(ev.pointer_id());
+        }
 
-    let class = Signal::derive(move || {
-        format!(
-            "ya-ya-popover animate__animated {} {}",
-            side.get().class_name(),
-            side.get().animate()
-        )
-    });
+        let rect = rect_of(&popover);
+        let (x, y) = (ev.client_x() as f64, ev.client_y() as f64);
+        grab.set_value((x - rect.x, y - rect.y));
+        set_dragging.set(Some((rect.x, rect.y)));
+    };
 
-    let on_close = move |_| {
-        close_cb.call(());
+    let on_drag = move |ev: ev::PointerEvent| {
+        if dragging.get_untracked().is_none() {
+            return;
+        }
+        let (dx, dy) = grab.get_value();
+        set_dragging.set(Some((ev.client_x() as f64 - dx, ev.client_y() as f64 - dy)));
+    };
+
+    let on_drag_end = move |ev: ev::PointerEvent| {
+        let (x, y) = if let Some(at) = dragging.get_untracked() {
+            at
+        } else {
+            return;
+        };
+        let pointer = (ev.client_x() as f64, ev.client_y() as f64);
+
+        log::debug!("popover.rs :: Dropping popover at {x}, {y}");
+        stack.update(|s| s.drop_at(&id, x, y, pointer, &dock_zone()));
+        set_dragging.set(None);
     };
 
     view! {
@@ -245,21 +575,52 @@ pub fn YaYaPopover(
             {..attrs}
             class=class
             style=pos_style
-            aria-live="polite"
             role="dialog"
+            aria-modal="false"
+            aria-labelledby=label_id.clone()
+            aria-describedby=content_id.clone()
             node_ref=popover_el
+            on:pointerdown=on_raise
+            on:focusin=on_focus_in
+            on:focusout=on_focus_out
+            on:keydown=on_keydown
         >
+            <span id=label_id class="ya-ya-visually-hidden">{label}</span>
+            <Show when=move || dragging.get().is_some()>
+                <div class="ya-ya-dock-zone" style:width=format!("{DOCK_ZONE_WIDTH}px")/>
+            </Show>
             <div class="ya-ya-popover-inner">
-                <button
-                    class="ya-ya-close-button"
-                    on:click=on_close
-                    title="Закрыть"
-                >
-                    "×"
-                </button>
+                {(!compact).then(|| view! {
+                    <div
+                        class="ya-ya-drag-handle"
+                        title="Перетащите, чтобы закрепить, или к правому краю, чтобы убрать в док"
+                        on:pointerdown=on_drag_start
+                        on:pointermove=on_drag
+                        on:pointerup=on_drag_end
+                        on:pointercancel=move |_| set_dragging.set(None)
+                    />
+                    <button
+                        class="ya-ya-pin-button"
+                        on:click=on_pin
+                        title=move || if is_detached() { "Вернуть к слову" } else { "Закрепить" }
+                        aria-pressed=move || is_detached().to_string()
+                    >
+                        "📌"
+                    </button>
+                    <button
+                        class="ya-ya-close-button"
+                        on:click=on_close
+                        title="Закрыть"
+                    >
+                        "×"
+                    </button>
+                })}
                 <div class="ya-ya-content"
+                    id=content_id
+                    tabindex="-1"
                     style:max-width=content_max_width
                     style:max-height=content_max_height
+                    node_ref=content_el
                 >
                     {children}
                 </div>
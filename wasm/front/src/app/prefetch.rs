@@ -0,0 +1,178 @@
+use std::{cell::RefCell, rc::Rc};
+
+use common::{
+    annotation::{Annotation, AnnotationRequest},
+    error::YaYaError,
+};
+use futures::future::{abortable, AbortHandle};
+use leptos::{spawn_local, Callable, Callback};
+use uuid::Uuid;
+
+pub type Delivery = Callback<(Uuid, Result<Annotation, YaYaError>)>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Response<T> {
+    /// Not arrived yet, `Some` trigger takes it once it does.
+    Waiting(Option<Uuid>),
+    /// Arrived before any trigger adopted it.
+    Done(T),
+    /// Dropped, a late response goes nowhere.
+    Aborted,
+}
+
+/// Who gets the response of a prefetched `request`, whichever of the response
+/// and the adopting trigger comes first.
+#[derive(Debug, Clone, PartialEq)]
+struct PrefetchState<R, T> {
+    request: R,
+    response: Response<T>,
+}
+
+impl<R: PartialEq, T> PrefetchState<R, T> {
+    fn new(request: R) -> Self {
+        Self {
+            request,
+            response: Response::Waiting(None),
+        }
+    }
+
+    fn is_for(&self, request: &R) -> bool {
+        &self.request == request
+    }
+
+    /// The response arrived, returns where to deliver it if a trigger already waits for it.
+    fn arrive(&mut self, res: T) -> Option<(Uuid, T)> {
+        match self.response {
+            Response::Waiting(Some(id)) => Some((id, res)),
+            Response::Waiting(None) => {
+                self.response = Response::Done(res);
+                None
+            }
+            Response::Done(_) | Response::Aborted => None,
+        }
+    }
+
+    /// The trigger `id` takes the response, returns it if it already arrived.
+    fn adopt(&mut self, id: Uuid) -> Option<(Uuid, T)> {
+        match std::mem::replace(&mut self.response, Response::Waiting(Some(id))) {
+            Response::Done(res) => Some((id, res)),
+            Response::Waiting(_) => None,
+            Response::Aborted => {
+                self.response = Response::Aborted;
+                None
+            }
+        }
+    }
+
+    fn abort(&mut self) {
+        self.response = Response::Aborted;
+    }
+}
+
+/// Speculative annotation request started while a mark is still pending.
+#[derive(Debug)]
+pub struct Prefetch {
+    handle: AbortHandle,
+    state: Rc<RefCell<PrefetchState<AnnotationRequest, Result<Annotation, YaYaError>>>>,
+    deliver: Delivery,
+}
+
+impl Prefetch {
+    pub fn start(request: AnnotationRequest, deliver: Delivery) -> Self {
+        let (response, handle) = abortable(request.clone().send(vec![]));
+        let state = Rc::new(RefCell::new(PrefetchState::new(request)));
+
+        let result_state = state.clone();
+        spawn_local(async move {
+            if let Ok(res) = response.await {
+                let delivery = result_state.borrow_mut().arrive(res);
+                if let Some(delivery) = delivery {
+                    deliver.call(delivery);
+                }
+            } else {
+                log::debug!("prefetch.rs :: Prefetch aborted");
+            }
+        });
+
+        Self {
+            handle,
+            state,
+            deliver,
+        }
+    }
+
+    pub fn is_for(&self, request: &AnnotationRequest) -> bool {
+        self.state.borrow().is_for(request)
+    }
+
+    /// Hands the result to the permanent trigger `id`, now or once it arrives.
+    pub fn adopt(self, id: Uuid) {
+        let delivery = self.state.borrow_mut().adopt(id);
+
+        if let Some(delivery) = delivery {
+            self.deliver.call(delivery);
+        }
+    }
+
+    /// Drops the result, whether it arrived or not.
+    ///
+    /// Abort is local only: the request already sent still runs to the end, and is billed.
+    pub fn abort(self) {
+        self.state.borrow_mut().abort();
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type State = PrefetchState<&'static str, &'static str>;
+
+    #[test]
+    fn matches_its_request_only() {
+        let state = State::new("синхротрон");
+
+        assert!(state.is_for(&"синхротрон"));
+        assert!(!state.is_for(&"синхрофазотрон"));
+    }
+
+    #[test]
+    fn delivers_on_adoption_when_already_arrived() {
+        let mut state = State::new("синхротрон");
+        let id = Uuid::new_v4();
+
+        assert_eq!(state.arrive("annotation"), None);
+        assert_eq!(state.adopt(id), Some((id, "annotation")));
+    }
+
+    #[test]
+    fn delivers_on_arrival_when_already_adopted() {
+        let mut state = State::new("синхротрон");
+        let id = Uuid::new_v4();
+
+        assert_eq!(state.adopt(id), None);
+        assert_eq!(state.arrive("annotation"), Some((id, "annotation")));
+    }
+
+    #[test]
+    fn keeps_the_response_until_adopted() {
+        let mut state = State::new("синхротрон");
+
+        assert_eq!(state.arrive("annotation"), None);
+        assert_eq!(state.response, Response::Done("annotation"));
+    }
+
+    #[test]
+    fn discards_the_response_once_aborted() {
+        let mut state = State::new("синхротрон");
+        state.abort();
+        assert_eq!(state.arrive("annotation"), None);
+
+        let mut state = State::new("синхротрон");
+        assert_eq!(state.arrive("annotation"), None);
+        state.abort();
+        assert_eq!(state.adopt(Uuid::new_v4()), None);
+        assert_eq!(state.response, Response::Aborted);
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+
+use common::{
+    annotation::{success_record, Annotation, AnnotationRequest, RequestKind},
+    card::AnnotationCard,
+    error::YaYaError,
+};
+use leptos::*;
+
+use super::article::Article;
+
+/// Chunks simplified at once, the rest wait for their turn.
+const CONCURRENT_CHUNKS: usize = 3;
+
+/// Chunks waiting for their simplification, at most `max` of them requested at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pool {
+    queue: VecDeque<usize>,
+    running: usize,
+    max: usize,
+}
+
+impl Pool {
+    /// Every one of the `count` chunks waits, in reading order.
+    pub fn new(max: usize, count: usize) -> Self {
+        Self {
+            queue: (0..count).collect(),
+            running: 0,
+            max,
+        }
+    }
+
+    /// Queues the chunk `at` again, ahead of the others: the reader is waiting for it.
+    pub fn retry(&mut self, at: usize) {
+        self.queue.retain(|queued| *queued != at);
+        self.queue.push_front(at);
+    }
+
+    /// The next chunk to request, `None` while `max` are requested or none is waiting.
+    pub fn next(&mut self) -> Option<usize> {
+        if self.running >= self.max {
+            return None;
+        }
+        let at = self.queue.pop_front()?;
+        self.running += 1;
+
+        Some(at)
+    }
+
+    /// One of the requested chunks got its answer.
+    pub fn done(&mut self) {
+        self.running = self.running.saturating_sub(1);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    request: AnnotationRequest,
+    answer: RwSignal<Option<Result<Annotation, YaYaError>>>,
+    /// Simplifications the reader did not understand, oldest first.
+    rejected: RwSignal<Vec<String>>,
+}
+
+/// Requests the waiting chunks while the pool allows, the answers request the next ones.
+fn pump(pool: StoredValue<Pool>, chunks: StoredValue<Vec<Chunk>>) {
+    while let Some(at) = pool.try_update_value(|p| p.next()).flatten() {
+        let Some(chunk) = chunks.try_with_value(|c| c.get(at).cloned()).flatten() else {
+            pool.update_value(|p| p.done());
+            continue;
+        };
+        let request = chunk.request.clone().send(chunk.rejected.get_untracked());
+
+        spawn_local(async move {
+            let answer = request.await;
+            // The reader view may be closed by now.
+            if chunk.answer.try_set(Some(answer)).is_none()
+                && pool.try_update_value(|p| p.done()).is_some()
+            {
+                pump(pool, chunks);
+            }
+        });
+    }
+}
+
+/// The article of the page with its plain-language version side by side, chunk by chunk.
+#[component]
+pub fn ReaderView(article: Article, #[prop(into)] on_close: Callback<()>) -> impl IntoView {
+    let Article {
+        title,
+        origin,
+        chunks,
+    } = article;
+
+    let chunks = chunks
+        .into_iter()
+        .map(|content| Chunk {
+            request: AnnotationRequest {
+                kind: RequestKind::Text,
+                content,
+                context: origin.clone(),
+            },
+            answer: create_rw_signal(None),
+            rejected: create_rw_signal(vec![]),
+        })
+        .collect::<Vec<_>>();
+    let total = chunks.len();
+    let ready = {
+        let answers = chunks.iter().map(|c| c.answer).collect::<Vec<_>>();
+        move || {
+            answers
+                .iter()
+                .filter(|a| a.with(|a| matches!(a, Some(Ok(_)))))
+                .count()
+        }
+    };
+
+    let rows = chunks.clone();
+    let chunks = store_value(chunks);
+    let pool = store_value(Pool::new(CONCURRENT_CHUNKS, total));
+    pump(pool, chunks);
+
+    let request_again = move |at: usize| {
+        chunks.with_value(|c| c[at].answer.set(None));
+        pool.update_value(|p| p.retry(at));
+        pump(pool, chunks);
+    };
+
+    let close_ref = create_node_ref::<html::Button>();
+    create_effect(move |_| {
+        if let Some(close) = close_ref.get() {
+            _ = close.focus();
+        }
+    });
+
+    view! {
+        <div
+            class="ya-ya-reader"
+            role="dialog"
+            aria-modal="true"
+            aria-labelledby="ya-ya-reader-title"
+            on:keydown=move |ev| {
+                if ev.key() == "Escape" {
+                    on_close.call(());
+                }
+            }
+        >
+            <header class="ya-ya-reader-header">
+                <h1 id="ya-ya-reader-title">{title}</h1>
+                <p class="ya-ya-reader-status" role="status">
+                    {move || format!("Упрощено {} из {total}", ready())}
+                </p>
+                <button
+                    class="ya-ya-button"
+                    node_ref=close_ref
+                    on:click=move |_| on_close.call(())
+                >
+                    "Вернуться к странице"
+                </button>
+            </header>
+            <ol class="ya-ya-reader-rows">
+                {rows
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(at, chunk)| {
+                        let Chunk { request, answer, rejected } = chunk;
+                        let original = request.content.clone();
+                        let on_feedback = move |good: bool| {
+                            let Some(Ok(shown)) = answer.get_untracked() else {
+                                return;
+                            };
+                            let id = shown.id;
+                            spawn_local(async move {
+                                if let Err(err) = success_record(id, good).await {
+                                    log::error!("reader.rs :: Failed to record feedback: {err:?}");
+                                }
+                            });
+                            if !good {
+                                rejected.update(|r| r.push(shown.annotation));
+                                request_again(at);
+                            }
+                        };
+
+                        view! {
+                            <li class="ya-ya-reader-row">
+                                <blockquote class="ya-ya-text-original ya-ya-reader-original">
+                                    {original}
+                                </blockquote>
+                                <div class="ya-ya-reader-simple">
+                                    <AnnotationCard
+                                        annotation=answer
+                                        on_feedback=on_feedback
+                                        on_retry=move |_| request_again(at)
+                                        follow_up=request
+                                        rejected
+                                    />
+                                </div>
+                            </li>
+                        }
+                    })
+                    .collect_view()}
+            </ol>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_a_few_at_once() {
+        let mut pool = Pool::new(2, 4);
+
+        assert_eq!(pool.next(), Some(0));
+        assert_eq!(pool.next(), Some(1));
+        assert_eq!(pool.next(), None);
+
+        pool.done();
+        assert_eq!(pool.next(), Some(2));
+        pool.done();
+        pool.done();
+        assert_eq!(pool.next(), Some(3));
+        assert_eq!(pool.next(), None);
+        pool.done();
+        assert_eq!(pool.next(), None);
+    }
+
+    #[test]
+    fn retries_go_first() {
+        let mut pool = Pool::new(1, 3);
+
+        assert_eq!(pool.next(), Some(0));
+        pool.retry(2);
+        pool.done();
+
+        assert_eq!(pool.next(), Some(2));
+        pool.done();
+        assert_eq!(pool.next(), Some(1));
+        pool.done();
+        assert_eq!(pool.next(), None);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use leptos::{document, window, Callable, Callback};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{IdleDeadline, Node, NodeIterator};
+
+use super::{
+    lexicon::{normalize, Lexicon},
+    util::{is_al_mounted, utf16_offset, words_map},
+};
+use crate::MOUNT;
+
+/// `NodeFilter.SHOW_TEXT`
+const SHOW_TEXT: u32 = 0x4;
+
+/// Stop stepping once the browser gives us less idle time than this, ms.
+const MIN_IDLE_TIME: f64 = 1.0;
+
+/// Share of the score coming from rarity, the rest comes from length.
+const RARITY_WEIGHT: f64 = 0.7;
+
+/// Words longer than this are all equally long for scoring.
+const MAX_SCORED_LENGTH: usize = 14;
+
+/// Text inside these elements is never scanned, however deep.
+const SKIP_SELECTOR: &str = "script, style, noscript, textarea, input, select, code, pre, button";
+
+const CONTENT_SELECTORS: &[&str] = &["main", "article", "[role=main]"];
+
+/// How hard the word is likely to be, between `0.0` and `1.0`,
+/// or `None` when it should never be highlighted.
+pub fn difficulty(lexicon: &Lexicon, word: &str, max_rank: usize) -> Option<f64> {
+    if lexicon.is_trivial(word, max_rank) || word.chars().next().is_some_and(char::is_uppercase) {
+        return None;
+    }
+
+    let rarity = lexicon
+        .rank(word)
+        .map(|r| r as f64 / lexicon.len() as f64)
+        .unwrap_or(1.0);
+    let length = word.chars().count().min(MAX_SCORED_LENGTH) as f64 / MAX_SCORED_LENGTH as f64;
+
+    Some(RARITY_WEIGHT * rarity + (1.0 - RARITY_WEIGHT) * length)
+}
+
+/// The element holding the main content of the page.
+pub fn content_root() -> Node {
+    CONTENT_SELECTORS
+        .iter()
+        .find_map(|s| document().query_selector(s).ok().flatten())
+        .map(Node::from)
+        .or_else(|| document().body().map(Node::from))
+        .unwrap_or_else(|| document().into())
+}
+
+/// The `count` best scored places, hardest first, skipping the ones in the `same_node` as a harder one.
+pub fn hardest<T>(
+    mut scored: Vec<(T, f64)>,
+    count: usize,
+    same_node: impl Fn(&T, &T) -> bool,
+) -> Vec<T> {
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    scored
+        .into_iter()
+        .fold(Vec::<T>::new(), |mut acc, (place, _)| {
+            if acc.len() < count && !acc.iter().any(|p| same_node(p, &place)) {
+                acc.push(place);
+            }
+            acc
+        })
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    node: Node,
+    pos: u32,
+    score: f64,
+}
+
+/// Incremental walk over the text of the page, keeping the hardest occurrence of every word.
+pub struct DifficultWordsScan {
+    iter: NodeIterator,
+    max_rank: usize,
+    best: HashMap<String, Candidate>,
+}
+
+impl DifficultWordsScan {
+    pub fn start(root: &Node, max_rank: usize) -> Result<Self, JsValue> {
+        let iter = document().create_node_iterator_with_what_to_show(root, SHOW_TEXT)?;
+
+        Ok(Self {
+            iter,
+            max_rank,
+            best: HashMap::new(),
+        })
+    }
+
+    /// Visits text nodes while the deadline allows, returns `true` once the walk is over.
+    pub fn step(&mut self, deadline: &IdleDeadline) -> Result<bool, JsValue> {
+        let lexicon = Lexicon::bundled();
+
+        while deadline.time_remaining() > MIN_IDLE_TIME {
+            let node = if let Some(node) = self.iter.next_node()? {
+                node
+            } else {
+                return Ok(true);
+            };
+
+            if Self::skip(&node) {
+                continue;
+            }
+
+            let text = node.text_content().unwrap_or_default();
+            for (start, _, word) in words_map(&text) {
+                if let Some(score) = difficulty(lexicon, &word, self.max_rank) {
+                    let entry = self.best.entry(normalize(&word)).or_insert(Candidate {
+                        node: node.clone(),
+                        pos: utf16_offset(&text, start),
+                        score,
+                    });
+                    entry.score = entry.score.max(score);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Positions of the `count` hardest words, at most one per text node.
+    pub fn hardest(self, count: usize) -> Vec<(Node, u32)> {
+        let scored = self
+            .best
+            .into_values()
+            .map(|c| ((c.node, c.pos), c.score))
+            .collect();
+
+        hardest(scored, count, |(a, _), (b, _)| a.is_same_node(Some(b)))
+    }
+
+    /// Runs the scan in idle callbacks, calling `on_done` with the hardest `count` words.
+    pub fn run_idle(self, count: usize, on_done: Callback<Vec<(Node, u32)>>) {
+        schedule(Rc::new(RefCell::new(Some(self))), count, on_done);
+    }
+
+    fn skip(node: &Node) -> bool {
+        let parent = if let Some(parent) = node.parent_element() {
+            parent
+        } else {
+            return true;
+        };
+
+        parent
+            .closest(format!("{SKIP_SELECTOR}, #{MOUNT}, [contenteditable]").as_str())
+            .ok()
+            .flatten()
+            .is_some()
+            || is_al_mounted(node)
+    }
+}
+
+fn schedule(
+    scan: Rc<RefCell<Option<DifficultWordsScan>>>,
+    count: usize,
+    on_done: Callback<Vec<(Node, u32)>>,
+) {
+    let cb = Closure::once_into_js(move |deadline: IdleDeadline| {
+        let done = scan
+            .borrow_mut()
+            .as_mut()
+            .map(|s| s.step(&deadline))
+            .unwrap_or(Ok(true));
+
+        match done {
+            Ok(false) => schedule(scan, count, on_done),
+            Ok(true) => {
+                if let Some(done) = scan.borrow_mut().take() {
+                    on_done.call(done.hardest(count));
+                }
+            }
+            Err(err) => log::error!("scan.rs :: Difficult words scan failed: {err:?}"),
+        }
+    });
+
+    if let Err(err) = window().request_idle_callback(cb.unchecked_ref()) {
+        log::error!("scan.rs :: Failed to request idle callback: {err:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rare_and_long_words_are_harder() {
+        let lexicon = Lexicon::bundled();
+
+        let common = difficulty(lexicon, "работа", 0).unwrap();
+        let rare = difficulty(lexicon, "синхротрон", 0).unwrap();
+        let rare_long = difficulty(lexicon, "электроэнцефалограмма", 0).unwrap();
+        assert!(common < rare);
+        assert!(rare < rare_long);
+        assert!(rare_long <= 1.0);
+    }
+
+    #[test]
+    fn trivial_and_proper_words_are_skipped() {
+        let lexicon = Lexicon::bundled();
+
+        assert_eq!(difficulty(lexicon, "работа", 100), None);
+        assert_eq!(difficulty(lexicon, "42", 0), None);
+        assert_eq!(difficulty(lexicon, "Синхротрон", 0), None);
+    }
+
+    #[test]
+    fn hardest_first_up_to_count() {
+        let scored = vec![((1, 0), 0.2), ((2, 0), 0.9), ((3, 4), 0.5), ((4, 0), 0.7)];
+
+        assert_eq!(
+            hardest(scored.clone(), 3, |a, b| a.0 == b.0),
+            vec![(2, 0), (4, 0), (3, 4)]
+        );
+        assert_eq!(hardest(scored.clone(), 1, |a, b| a.0 == b.0), vec![(2, 0)]);
+        assert_eq!(hardest(scored, 0, |a, b| a.0 == b.0), vec![]);
+    }
+
+    #[test]
+    fn one_word_per_node() {
+        let scored = vec![((1, 0), 0.9), ((1, 12), 0.8), ((2, 3), 0.1)];
+
+        assert_eq!(hardest(scored, 2, |a, b| a.0 == b.0), vec![(1, 0), (2, 3)]);
+    }
+}
@@ -0,0 +1,242 @@
+use uuid::Uuid;
+
+use super::placement::Rect;
+
+/// How a popover is positioned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchoring {
+    /// Follows its mark.
+    Mark,
+    /// Stays at a viewport position, even when the mark scrolls away.
+    Pinned { x: f64, y: f64 },
+    /// Stacked in the dock along the right edge of the viewport.
+    Docked,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Layer {
+    id: Uuid,
+    /// Opening sequence, later popovers avoid earlier ones.
+    opened: usize,
+    anchoring: Anchoring,
+    /// Where the popover was last drawn, in viewport coordinates.
+    rect: Option<Rect>,
+}
+
+/// Open popovers ordered by focus, the last one is on top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PopoverStack {
+    max_open: usize,
+    opened: usize,
+    layers: Vec<Layer>,
+}
+
+impl PopoverStack {
+    pub fn new(max_open: usize) -> Self {
+        Self {
+            max_open,
+            opened: 0,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Applies on the next [`PopoverStack::sync`].
+    pub fn set_max_open(&mut self, max_open: usize) {
+        self.max_open = max_open;
+    }
+
+    /// Follows the open popovers: new ones go on top, closed ones are dropped.
+    ///
+    /// Returns the popovers to close to stay within the cap, unpinned and least recently focused first.
+    pub fn sync(&mut self, open: &[Uuid]) -> Vec<Uuid> {
+        self.layers.retain(|l| open.contains(&l.id));
+
+        for id in open {
+            if self.layer(id).is_none() {
+                self.opened += 1;
+                self.layers.push(Layer {
+                    id: *id,
+                    opened: self.opened,
+                    anchoring: Anchoring::Mark,
+                    rect: None,
+                });
+            }
+        }
+
+        let excess = self.layers.len().saturating_sub(self.max_open);
+        let mut evicted = self
+            .layers
+            .iter()
+            .filter(|l| l.anchoring == Anchoring::Mark)
+            .chain(
+                self.layers
+                    .iter()
+                    .filter(|l| l.anchoring != Anchoring::Mark),
+            )
+            .map(|l| l.id)
+            .take(excess)
+            .collect::<Vec<_>>();
+
+        // The popover that was just opened is never the one to go.
+        if let Some(newest) = self.layers.iter().max_by_key(|l| l.opened).map(|l| l.id) {
+            if evicted.contains(&newest) {
+                evicted.retain(|id| id != &newest);
+                if let Some(other) = self
+                    .layers
+                    .iter()
+                    .map(|l| l.id)
+                    .find(|id| id != &newest && !evicted.contains(id))
+                {
+                    evicted.push(other);
+                }
+            }
+        }
+
+        self.layers.retain(|l| !evicted.contains(&l.id));
+        evicted
+    }
+
+    /// Moves the popover on top.
+    pub fn raise(&mut self, id: &Uuid) {
+        if let Some(at) = self.layers.iter().position(|l| &l.id == id) {
+            let layer = self.layers.remove(at);
+            self.layers.push(layer);
+        }
+    }
+
+    /// Position in the focus order, 0 is at the bottom.
+    pub fn z_index(&self, id: &Uuid) -> usize {
+        self.layers.iter().position(|l| &l.id == id).unwrap_or(0)
+    }
+
+    pub fn anchoring(&self, id: &Uuid) -> Anchoring {
+        self.layer(id)
+            .map(|l| l.anchoring)
+            .unwrap_or(Anchoring::Mark)
+    }
+
+    pub fn set_anchoring(&mut self, id: &Uuid, anchoring: Anchoring) {
+        if let Some(layer) = self.layer_mut(id) {
+            layer.anchoring = anchoring;
+        }
+    }
+
+    /// Records where the popover was drawn, `None` when it is hidden.
+    pub fn placed(&mut self, id: &Uuid, rect: Option<Rect>) {
+        if let Some(layer) = self.layer_mut(id) {
+            layer.rect = rect;
+        }
+    }
+
+    /// Pins the popover where it was dropped, or docks it when dropped on the dock.
+    pub fn drop_at(&mut self, id: &Uuid, x: f64, y: f64, pointer: (f64, f64), dock: &Rect) {
+        let (px, py) = pointer;
+        let on_dock = px >= dock.x && px <= dock.right() && py >= dock.y && py <= dock.bottom();
+
+        self.set_anchoring(
+            id,
+            if on_dock {
+                Anchoring::Docked
+            } else {
+                Anchoring::Pinned { x, y }
+            },
+        );
+        self.raise(id);
+    }
+
+    /// Rects a popover following its mark should stay clear of: every popover
+    /// opened before it, and every pinned or docked one.
+    pub fn obstacles(&self, id: &Uuid) -> Vec<Rect> {
+        let opened = self.layer(id).map(|l| l.opened).unwrap_or(usize::MAX);
+
+        self.layers
+            .iter()
+            .filter(|l| &l.id != id)
+            .filter(|l| l.opened < opened || l.anchoring != Anchoring::Mark)
+            .filter_map(|l| l.rect)
+            .collect()
+    }
+
+    /// Distance from the top of the dock to the docked popover.
+    pub fn dock_offset(&self, id: &Uuid, gap: f64) -> f64 {
+        let mut docked = self
+            .layers
+            .iter()
+            .filter(|l| l.anchoring == Anchoring::Docked)
+            .collect::<Vec<_>>();
+        docked.sort_by_key(|l| l.opened);
+
+        docked
+            .iter()
+            .take_while(|l| &l.id != id)
+            .map(|l| l.rect.map(|r| r.height).unwrap_or_default() + gap)
+            .sum()
+    }
+
+    fn layer(&self, id: &Uuid) -> Option<&Layer> {
+        self.layers.iter().find(|l| &l.id == id)
+    }
+
+    fn layer_mut(&mut self, id: &Uuid) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|l| &l.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<Uuid> {
+        (0..count).map(|_| Uuid::new_v4()).collect()
+    }
+
+    #[test]
+    fn focus_sets_z_order() {
+        let ids = ids(3);
+        let mut stack = PopoverStack::new(3);
+        assert!(stack.sync(&ids).is_empty());
+
+        assert_eq!(stack.z_index(&ids[2]), 2);
+        stack.raise(&ids[0]);
+        assert_eq!(stack.z_index(&ids[0]), 2);
+        assert_eq!(stack.z_index(&ids[2]), 1);
+    }
+
+    #[test]
+    fn cap_evicts_unpinned_least_recently_focused() {
+        let ids = ids(4);
+        let mut stack = PopoverStack::new(3);
+        _ = stack.sync(&ids[..3]);
+        stack.set_anchoring(&ids[0], Anchoring::Pinned { x: 0.0, y: 0.0 });
+        stack.raise(&ids[1]);
+
+        assert_eq!(stack.sync(&ids), vec![ids[2]]);
+        assert!(stack.sync(&[ids[0], ids[1], ids[3]]).is_empty());
+    }
+
+    #[test]
+    fn newest_is_never_evicted() {
+        let ids = ids(2);
+        let mut stack = PopoverStack::new(1);
+        _ = stack.sync(&ids[..1]);
+        stack.set_anchoring(&ids[0], Anchoring::Docked);
+
+        assert_eq!(stack.sync(&ids), vec![ids[0]]);
+    }
+
+    #[test]
+    fn later_popovers_avoid_earlier_ones() {
+        let ids = ids(2);
+        let mut stack = PopoverStack::new(3);
+        _ = stack.sync(&ids);
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+        stack.placed(&ids[0], Some(rect));
+        stack.placed(&ids[1], Some(rect));
+
+        assert_eq!(stack.obstacles(&ids[1]), vec![rect]);
+        assert!(stack.obstacles(&ids[0]).is_empty());
+
+        stack.set_anchoring(&ids[1], Anchoring::Docked);
+        assert_eq!(stack.obstacles(&ids[0]), vec![rect]);
+    }
+}
@@ -0,0 +1,199 @@
+use common::annotation::RequestKind;
+use leptos::{document, window};
+use uuid::Uuid;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Node, Range};
+
+use super::mark::{PendingTrigger, PermanentTrigger, Trigger};
+
+/// Text the reader chose from the context menu: a selection, a link or an image description.
+///
+/// The page is left as is, only the anchors of the popover are added around the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMark {
+    pub content: String,
+    /// Element the popover is placed against.
+    pub mark: Element,
+    pub anchors: (Element, Element),
+    /// Text nodes the anchors split in two, joined back on unmount.
+    splits: Vec<(Node, Node)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextPermanentTrigger {
+    pub id: Uuid,
+    pub text: TextMark,
+}
+
+fn anchor() -> Result<Element, JsValue> {
+    let anchor = document().create_element("span")?;
+    anchor.set_attribute("class", "ya-ya-anchor")?;
+
+    Ok(anchor)
+}
+
+/// Inserts an anchor where `at` is collapsed, with the two halves of the text node it split.
+fn insert_anchor(at: &Range) -> Option<(Element, Option<(Node, Node)>)> {
+    let container = at.start_container().ok()?;
+    let anchor = anchor().ok()?;
+    at.insert_node(&anchor).ok()?;
+
+    let split = (container.node_type() == Node::TEXT_NODE)
+        .then(|| anchor.next_sibling())
+        .flatten()
+        .filter(|after| after.node_type() == Node::TEXT_NODE)
+        .map(|after| (container, after));
+
+    Some((anchor, split))
+}
+
+impl TextMark {
+    /// Anchors the selected `range` of `content`, placing the popover against the element containing it.
+    pub fn on_range(range: &Range, content: String) -> Option<Self> {
+        let content = content.trim().to_string();
+        if content.is_empty() {
+            return None;
+        }
+
+        let container = range.common_ancestor_container().ok()?;
+        let mark = match container.dyn_ref::<Element>() {
+            Some(element) => element.clone(),
+            None => container.parent_element()?,
+        };
+
+        // The end first, inserting at the start moves the end.
+        let end = range.clone_range();
+        end.collapse_with_to_start(false);
+        let (after, end_split) = insert_anchor(&end)?;
+
+        let start = range.clone_range();
+        start.collapse_with_to_start(true);
+        let (before, start_split) = insert_anchor(&start)?;
+
+        Some(Self {
+            content,
+            mark,
+            anchors: (before, after),
+            splits: start_split.into_iter().chain(end_split).collect(),
+        })
+    }
+
+    /// Anchors `element`, a link or an image, explaining its `content`.
+    pub fn on_element(element: &Element, content: String) -> Option<Self> {
+        let content = content.trim().to_string();
+        if content.is_empty() {
+            return None;
+        }
+
+        let before = anchor().ok()?;
+        let after = anchor().ok()?;
+        element.before_with_node_1(&before).ok()?;
+        element.after_with_node_1(&after).ok()?;
+
+        Some(Self {
+            content,
+            mark: element.clone(),
+            anchors: (before, after),
+            splits: vec![],
+        })
+    }
+
+    /// Page origin, the context of [`RequestKind::Text`].
+    pub fn context(&self) -> String {
+        window().location().origin().unwrap_or_default()
+    }
+
+    pub fn unmount(&self) -> Result<(), JsValue> {
+        self.anchors.0.remove();
+        self.anchors.1.remove();
+
+        // Only the nodes split here, the page may keep adjacent text nodes of its own. The start
+        // comes first, it splits the node the end split when both are in one.
+        for (before, after) in &self.splits {
+            if before.next_sibling().as_ref() != Some(after) {
+                continue;
+            }
+            let joined =
+                before.node_value().unwrap_or_default() + &after.node_value().unwrap_or_default();
+            before.set_node_value(Some(&joined));
+            if let Some(parent) = after.parent_node() {
+                parent.remove_child(after)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PendingTrigger for TextMark {
+    fn kind(&self) -> RequestKind {
+        RequestKind::Text
+    }
+
+    fn content(&self) -> String {
+        self.content.clone()
+    }
+
+    fn context(&self) -> String {
+        TextMark::context(self)
+    }
+
+    /// Chosen from the menu, there is no timer to animate.
+    fn start_animation(&self) -> Result<(), JsValue> {
+        Ok(())
+    }
+
+    fn make_permanent(&self, id: Uuid) -> Result<PermanentTrigger, JsValue> {
+        Ok(PermanentTrigger::new(TextPermanentTrigger {
+            id,
+            text: self.clone(),
+        }))
+    }
+
+    fn is_same(&self, _node: Node, _pos: u32) -> bool {
+        false
+    }
+
+    fn contains(&self, _node: &Node) -> bool {
+        false
+    }
+
+    fn unmount(&self) -> Result<(), JsValue> {
+        TextMark::unmount(self)
+    }
+}
+
+impl Trigger for TextPermanentTrigger {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn kind(&self) -> RequestKind {
+        RequestKind::Text
+    }
+
+    fn content(&self) -> String {
+        self.text.content.clone()
+    }
+
+    fn context(&self) -> String {
+        self.text.context()
+    }
+
+    fn anchors(&self) -> (Element, Element, Element) {
+        (
+            self.text.anchors.0.clone(),
+            self.text.mark.clone(),
+            self.text.anchors.1.clone(),
+        )
+    }
+
+    /// Only the popover reopens it, the mark may be a whole paragraph.
+    fn contains(&self, _node: &Node) -> bool {
+        false
+    }
+
+    fn unmount(&self) -> Result<(), JsValue> {
+        self.text.unmount()
+    }
+}
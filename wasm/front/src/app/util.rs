@@ -1,7 +1,10 @@
+use common::sentences::split_sentences;
+use leptos::document;
+use unicode_segmentation::UnicodeSegmentation;
 use wasm_bindgen::JsCast;
 use web_sys::{Element, Node};
 
-use super::{PENDING_ATTRIBUTE_WORD, TRIGGER_ATTRIBUTE_WORD};
+use super::{DIFFICULT_ATTRIBUTE_WORD, PENDING_ATTRIBUTE_WORD, TRIGGER_ATTRIBUTE_WORD};
 
 pub fn words_map(text: &str) -> Vec<(usize, usize, String)> {
     text.chars()
@@ -32,6 +35,7 @@ pub fn is_al_mounted(node: &Node) -> bool {
     }) {
         if element.has_attribute(PENDING_ATTRIBUTE_WORD)
             || element.has_attribute(TRIGGER_ATTRIBUTE_WORD)
+            || element.has_attribute(DIFFICULT_ATTRIBUTE_WORD)
         {
             return true;
         }
@@ -64,3 +68,112 @@ pub fn text_node(node: Node) -> Option<Node> {
         _ => None,
     }
 }
+
+/// A few words around `word`, taken from the closest ancestor of `root` that contains it.
+pub fn word_context(root: &Element, word: &str) -> String {
+    let mut current_node: Node = root.clone().into();
+
+    while let Some(element) = current_node.parent_node() {
+        if let Some(parent_text) = element.text_content() {
+            let parent_text_without_spaces: String = parent_text.split_whitespace().collect();
+            if parent_text_without_spaces.contains(word) {
+                let words: Vec<&str> = parent_text.unicode_words().collect();
+                if let Some(word_pos) = words.iter().position(|&w| w == word) {
+                    let start = word_pos.saturating_sub(3);
+                    let end = (word_pos + 4).min(words.len());
+                    return words[start..end].join(" ");
+                }
+            }
+        }
+        current_node = element;
+    }
+
+    // Fallback to the original root if no parent contains the word
+    let text = root.text_content().unwrap_or_default();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let word_pos = words.iter().position(|&w| w == word).unwrap_or(0);
+
+    let start = word_pos.saturating_sub(3);
+    let end = (word_pos + 4).min(words.len());
+
+    words[start..end].join(" ")
+}
+
+/// Elements whose text is the context of a word looked up inside an explanation.
+const CONTEXT_BLOCKS: &str = "p, li, blockquote, h1, h2, h3, h4, h5, h6, .ya-ya-pre";
+
+/// The word under the point inside `target`, with the sentence it is in.
+///
+/// Works inside shadow roots, where the document caret cannot reach.
+pub fn word_at_point(target: &Element, x: f64, y: f64) -> Option<(String, String)> {
+    let children = target.child_nodes();
+    let range = document().create_range().ok()?;
+
+    for n in 0..children.length() {
+        let node = children.get(n)?;
+        if node.node_type() != 3 {
+            continue;
+        }
+        let text = node.text_content().unwrap_or_default();
+
+        for (start, end, word) in words_map(&text) {
+            if !word.chars().all(|c| c.is_alphabetic()) {
+                continue;
+            }
+
+            range.set_start(&node, utf16_offset(&text, start)).ok()?;
+            range.set_end(&node, utf16_offset(&text, end)).ok()?;
+            let rect = range.get_bounding_client_rect();
+
+            if x >= rect.left() && x <= rect.right() && y >= rect.top() && y <= rect.bottom() {
+                let block = target
+                    .closest(CONTEXT_BLOCKS)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| target.clone());
+                let paragraph = block.text_content().unwrap_or_default();
+                let context = split_sentences(&paragraph)
+                    .into_iter()
+                    .find(|s| s.contains(word.as_str()))
+                    .unwrap_or(paragraph.trim())
+                    .to_string();
+                return Some((word, context));
+            }
+        }
+    }
+
+    None
+}
+
+/// DOM offset in `text`, counted in UTF-16 units, of the char at index `chars`.
+pub fn utf16_offset(text: &str, chars: usize) -> u32 {
+    text.chars().take(chars).map(|c| c.len_utf16() as u32).sum()
+}
+
+/// Index of the char at the DOM offset `utf16` in `text`, the inverse of [`utf16_offset`].
+pub fn char_index(text: &str, utf16: u32) -> usize {
+    text.chars()
+        .scan(0, |units, c| {
+            let at = *units;
+            *units += c.len_utf16() as u32;
+            Some(at)
+        })
+        .take_while(|at| *at < utf16)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_offsets_around_astral_chars() {
+        let text = "😀 синхротрон";
+
+        assert_eq!(utf16_offset(text, 2), 3);
+        assert_eq!(char_index(text, 3), 2);
+        for at in 0..=text.chars().count() {
+            assert_eq!(char_index(text, utf16_offset(text, at)), at);
+        }
+    }
+}
@@ -1,15 +1,14 @@
-use std::str::FromStr;
-
-use common::{annotation::Annotation, error::YaYaError};
+use common::{annotation::RequestKind, settings::Settings};
 use leptos::document;
-use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
-use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::JsValue;
 use web_sys::{Element, Node};
 
 use super::{
-    util::*, BRAND_COLOR, MARK_ROOT_ATTRIBUTE, PENDING_ATTRIBUTE_WORD, TRIGGER_ANIMATED_TIMER,
-    TRIGGER_ATTRIBUTE_WORD,
+    lexicon::Lexicon,
+    mark::{PendingTrigger, PermanentTrigger, Trigger},
+    util::*,
+    DIFFICULT_ATTRIBUTE_WORD, MARK_ROOT_ATTRIBUTE, PENDING_ATTRIBUTE_WORD, TRIGGER_ATTRIBUTE_WORD,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -19,7 +18,6 @@ pub struct WordMark {
     pub word_pos: usize,
     pub root: Element,
     pub mark: Element,
-    pub time: f64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -31,8 +29,6 @@ pub struct WordPermanentTrigger {
     pub root: Element,
     pub mark: Element,
     pub anchors: (Element, Element),
-    pub annotation: Option<Result<Annotation, YaYaError>>,
-    pub feedback: bool,
 }
 
 impl WordMark {
@@ -57,7 +53,7 @@ impl WordMark {
             return false;
         };
 
-        let char_at = pos as usize;
+        let char_at = char_index(&text, pos);
 
         log::debug!("word.rs :: Creating a map of words from the text content");
         let words_map = words_map(&text);
@@ -84,7 +80,25 @@ impl WordMark {
                 .unwrap_or_default()
     }
 
-    pub fn mount_on_text(node: Node, pos: u32) -> Option<Self> {
+    pub fn mount_on_text(node: Node, pos: u32, settings: &Settings) -> Option<Self> {
+        Self::mount(node, pos, settings, PENDING_ATTRIBUTE_WORD)
+    }
+
+    /// Mounts a pre-highlighted mark found by the difficult words scan.
+    pub fn mount_difficult(node: Node, pos: u32, settings: &Settings) -> Option<Self> {
+        Self::mount(node, pos, settings, DIFFICULT_ATTRIBUTE_WORD)
+    }
+
+    /// Mounts the word the reader chose from the context menu, however common it is.
+    pub fn mount_chosen(node: Node, pos: u32, settings: &Settings) -> Option<Self> {
+        let settings = Settings {
+            frequency_rank: 0,
+            ..*settings
+        };
+        Self::mount(node, pos, &settings, PENDING_ATTRIBUTE_WORD)
+    }
+
+    fn mount(node: Node, pos: u32, settings: &Settings, attribute: &str) -> Option<Self> {
         log::debug!("word.rs :: Fetching text node from the provided node");
         let text_node = text_node(node.clone())?;
 
@@ -96,7 +110,7 @@ impl WordMark {
 
         log::debug!("word.rs :: Fetching text content from the node");
         let text = node.text_content()?;
-        let char_at = pos as usize;
+        let char_at = char_index(&text, pos);
 
         log::debug!("word.rs :: Creating a map of words from the text content");
         let words_map = words_map(&text);
@@ -118,6 +132,12 @@ impl WordMark {
             return None;
         }
 
+        log::debug!("word.rs :: Checking if the extracted word is too common to explain");
+        if Lexicon::bundled().is_trivial(&wd, settings.frequency_rank) {
+            log::debug!("word.rs :: Word is trivial, returning None");
+            return None;
+        }
+
         log::debug!("word.rs :: Creating text nodes for before and after the word");
         let text_node_before = document().create_text_node(&text_before);
         let text_node_after = document().create_text_node(&text_after);
@@ -125,18 +145,24 @@ impl WordMark {
         log::debug!("word.rs :: Creating a mark element for the word");
         let mark = document().create_element("mark").ok()?;
 
-        log::debug!("word.rs :: Setting pending attribute on the mark element");
-        mark.set_attribute(PENDING_ATTRIBUTE_WORD, "0").ok()?;
+        log::debug!("word.rs :: Setting {attribute} attribute on the mark element");
+        mark.set_attribute(attribute, "0").ok()?;
 
         log::debug!("word.rs :: Setting style attributes on the mark element");
         mark.set_attribute(
             "style",
-            format!("--pending-animation-duration: {TRIGGER_ANIMATED_TIMER}ms; --mark-background-color: rgba({r}, {g}, {b}, 0.75)",
-                r=BRAND_COLOR[0],
-                g=BRAND_COLOR[1],
-                b=BRAND_COLOR[2]
-            ).as_str(),
-        ).ok()?;
+            format!(
+                "--pending-animation-duration: {duration}ms; --mark-background-color: {color}",
+                duration = if settings.theme.animates() {
+                    settings.trigger_delay
+                } else {
+                    0
+                },
+                color = settings.theme.mark_color()
+            )
+            .as_str(),
+        )
+        .ok()?;
 
         log::debug!("word.rs :: Setting text content on the mark element");
         mark.set_text_content(Some(&wd));
@@ -169,7 +195,6 @@ impl WordMark {
             word_pos,
             root,
             mark,
-            time: 0.0,
         })
     }
 
@@ -197,15 +222,20 @@ impl WordMark {
         Ok(())
     }
 
-    pub fn tick_timer(&mut self, delta: f64) -> bool {
-        if self.time == 0.0 {
-            self.mark
-                .set_attribute(PENDING_ATTRIBUTE_WORD, "1")
-                .unwrap();
-        }
+    pub fn word(&self) -> String {
+        self.mark
+            .text_content()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    }
+
+    pub fn context(&self) -> String {
+        word_context(&self.root, &self.word())
+    }
 
-        self.time += delta;
-        self.time >= TRIGGER_ANIMATED_TIMER
+    pub fn start_animation(&self) -> Result<(), JsValue> {
+        self.mark.set_attribute(PENDING_ATTRIBUTE_WORD, "1")
     }
 }
 
@@ -214,6 +244,7 @@ impl WordPermanentTrigger {
         let mark = pending.mark.clone();
 
         mark.remove_attribute(PENDING_ATTRIBUTE_WORD)?;
+        mark.remove_attribute(DIFFICULT_ATTRIBUTE_WORD)?;
         mark.set_attribute(TRIGGER_ATTRIBUTE_WORD, id.to_string().as_str())?;
         mark.set_attribute("id", format!("mark-{id}").as_str())?;
 
@@ -233,8 +264,6 @@ impl WordPermanentTrigger {
             end: pending.end,
             word_pos: pending.word_pos,
             root: pending.root.clone(),
-            annotation: None,
-            feedback: false,
         })
     }
 
@@ -262,31 +291,6 @@ impl WordPermanentTrigger {
         Ok(())
     }
 
-    pub fn id(node: Node) -> Option<Uuid> {
-        let text_node = text_node(node)?;
-        let text = Some(text_node.text_content()?);
-
-        let mut current_node = text_node;
-
-        while let Some(element) = current_node.dyn_ref::<Element>().cloned().or_else(|| {
-            current_node
-                .parent_node()
-                .and_then(|p| p.dyn_ref::<Element>().cloned())
-        }) {
-            if let Some(id) = element.get_attribute(TRIGGER_ATTRIBUTE_WORD) {
-                return Uuid::from_str(&id).ok();
-            } else if text != element.text_content() {
-                break;
-            } else if let Some(up) = element.parent_node() {
-                current_node = up
-            } else {
-                break;
-            }
-        }
-
-        None
-    }
-
     pub fn word(&self) -> String {
         self.mark
             .text_content()
@@ -296,32 +300,74 @@ impl WordPermanentTrigger {
     }
 
     pub fn context(&self) -> String {
-        let mut current_node: Node = self.root.clone().into();
-        let word = self.word();
-
-        while let Some(element) = current_node.parent_node() {
-            if let Some(parent_text) = element.text_content() {
-                let parent_text_without_spaces: String = parent_text.split_whitespace().collect();
-                if parent_text_without_spaces.contains(&word) {
-                    let words: Vec<&str> = parent_text.unicode_words().collect();
-                    if let Some(word_pos) = words.iter().position(|&w| w == word) {
-                        let start = word_pos.saturating_sub(3);
-                        let end = (word_pos + 4).min(words.len());
-                        return words[start..end].join(" ");
-                    }
-                }
-            }
-            current_node = element;
-        }
+        word_context(&self.root, &self.word())
+    }
+}
+
+impl PendingTrigger for WordMark {
+    fn kind(&self) -> RequestKind {
+        RequestKind::Word
+    }
+
+    fn content(&self) -> String {
+        self.word()
+    }
+
+    fn context(&self) -> String {
+        WordMark::context(self)
+    }
+
+    fn start_animation(&self) -> Result<(), JsValue> {
+        WordMark::start_animation(self)
+    }
+
+    fn make_permanent(&self, id: Uuid) -> Result<PermanentTrigger, JsValue> {
+        WordPermanentTrigger::make_permanent(self, id).map(PermanentTrigger::new)
+    }
+
+    fn is_same(&self, node: Node, pos: u32) -> bool {
+        WordMark::is_same(self, node, pos)
+    }
+
+    fn contains(&self, node: &Node) -> bool {
+        self.mark.contains(Some(node))
+    }
+
+    fn unmount(&self) -> Result<(), JsValue> {
+        WordMark::unmount(self)
+    }
+}
+
+impl Trigger for WordPermanentTrigger {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn kind(&self) -> RequestKind {
+        RequestKind::Word
+    }
+
+    fn content(&self) -> String {
+        self.word()
+    }
 
-        // Fallback to the original root if no parent contains the word
-        let text = self.root.text_content().unwrap_or_default();
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let word_pos = words.iter().position(|&w| w == word).unwrap_or(0);
+    fn context(&self) -> String {
+        WordPermanentTrigger::context(self)
+    }
 
-        let start = word_pos.saturating_sub(3);
-        let end = (word_pos + 4).min(words.len());
+    fn anchors(&self) -> (Element, Element, Element) {
+        (
+            self.anchors.0.clone(),
+            self.mark.clone(),
+            self.anchors.1.clone(),
+        )
+    }
+
+    fn contains(&self, node: &Node) -> bool {
+        self.mark.contains(Some(node))
+    }
 
-        words[start..end].join(" ")
+    fn unmount(&self) -> Result<(), JsValue> {
+        WordPermanentTrigger::unmount(self)
     }
 }
@@ -0,0 +1,253 @@
+use common::annotation::Annotation;
+use common::card::AnnotationCard;
+use common::error::YaYaError;
+use common::settings::use_settings;
+use leptos::*;
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use super::lexicon::Lexicon;
+use super::mark::PermanentTrigger;
+use super::nested::{Lookup, Step, Trail};
+use super::popover::YaYaPopover;
+use super::stack::PopoverStack;
+use super::util::word_at_point;
+
+/// Clicks on these never start a nested lookup.
+const NO_LOOKUP: &str = "a, button, input, form, h3";
+
+#[component]
+pub fn YaTriggerPopover(
+    trigger: PermanentTrigger,
+    stack: RwSignal<PopoverStack>,
+    #[prop(into)] annotation: Signal<Option<Result<Annotation, YaYaError>>>,
+    #[prop(into)] close_cb: Callback<(Uuid, Option<bool>)>,
+    #[prop(into)] regenerate_cb: Callback<Uuid>,
+    /// Index of the shown version and the number of versions.
+    #[prop(into)]
+    version: Signal<(usize, usize)>,
+    /// Versions before the shown one, oldest first.
+    #[prop(into)]
+    rejected: Signal<Vec<String>>,
+    #[prop(into)] show_cb: Callback<(Uuid, usize)>,
+    /// Reads the explanation aloud when it arrives.
+    #[prop(optional, into)]
+    auto_read: MaybeSignal<bool>,
+) -> impl IntoView {
+    let id = trigger.id();
+    let elements = trigger.anchors();
+    let word = trigger.content();
+    let request = trigger.request();
+    let trigger = store_value(trigger);
+
+    let on_close = move |_| {
+        close_cb.call((id, annotation.get().map(|_| Some(false)).unwrap_or(None)));
+    };
+
+    let on_feedback = move |good: bool| {
+        if good {
+            close_cb.call((id, Some(true)));
+        } else {
+            regenerate_cb.call(id);
+        }
+    };
+
+    let label = format!("Пояснение: {word}");
+    let settings = use_settings();
+    let trail = create_rw_signal(Trail::new(word.clone()));
+    let depth = create_memo(move |_| trail.with(|t| t.depth()));
+
+    let lookup_action =
+        create_action(|(depth, lookup, rejected): &(usize, Lookup, Vec<String>)| {
+            let depth = *depth;
+            let word = lookup.word.clone();
+            let request = lookup.request().send(rejected.clone());
+            async move { (depth, word, request.await) }
+        });
+
+    let lookup_value = lookup_action.value();
+    create_render_effect(move |_| {
+        if let Some((depth, word, annotation)) = lookup_value.get() {
+            trail.update(|t| _ = t.annotated(depth, &word, annotation));
+        }
+    });
+
+    let annotate_lookup = move |depth: usize| {
+        let mut next = None;
+        trail.update(|t| next = t.regenerate(depth));
+        if let Some((lookup, rejected)) = next {
+            log::debug!(
+                "ya_trigger.rs :: Looking up {:?} at depth {}",
+                lookup.word,
+                depth
+            );
+            lookup_action.dispatch((depth, lookup, rejected));
+        }
+    };
+
+    let on_lookup = move |ev: ev::MouseEvent| {
+        let Some(target) = ev.target().and_then(|t| t.dyn_into::<Element>().ok()) else {
+            return;
+        };
+        let in_explanation = target.closest(".ya-ya-pre").ok().flatten().is_some();
+        let excluded = target.closest(NO_LOOKUP).ok().flatten().is_some();
+        if !in_explanation || excluded {
+            return;
+        }
+
+        let Some((word, context)) =
+            word_at_point(&target, ev.client_x() as f64, ev.client_y() as f64)
+        else {
+            return;
+        };
+        if Lexicon::bundled().is_trivial(&word, settings.get_untracked().frequency_rank) {
+            return;
+        }
+
+        let mut step = Step::TooDeep;
+        trail.update(|t| step = t.open(word, context));
+        match step {
+            Step::Opened(depth) => annotate_lookup(depth),
+            Step::Back(depth) => {
+                log::debug!("ya_trigger.rs :: Lookup cycle, back to depth {}", depth)
+            }
+            Step::TooDeep => log::debug!("ya_trigger.rs :: Lookups are too deep, ignoring"),
+        }
+    };
+
+    let crumbs = move || {
+        trail.with(|t| {
+            std::iter::once(t.root().to_string())
+                .chain(t.lookups().iter().map(|l| l.word.clone()))
+                .enumerate()
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let nested = move || {
+        let depth = depth.get();
+        (depth > 0).then(|| {
+            let heading = trail.with_untracked(|t| t.lookup(depth).map(|l| l.word.clone()));
+            let annotation = Signal::derive(move || {
+                trail.with(|t| t.lookup(depth).and_then(|l| l.annotation.clone()))
+            });
+
+            let on_feedback = move |good: bool| {
+                if let Some(Ok(answer)) = annotation.get_untracked() {
+                    let record = trigger.with_value(|t| t.feedback(answer.id, good));
+                    spawn_local(async move {
+                        _ = record.await;
+                    });
+                }
+                if good {
+                    trail.update(|t| t.back(depth - 1));
+                } else {
+                    annotate_lookup(depth);
+                }
+            };
+
+            view! {
+                <AnnotationCard
+                    annotation
+                    on_feedback
+                    on_retry=move |_| annotate_lookup(depth)
+                    heading=heading.unwrap_or_default()
+                />
+            }
+        })
+    };
+
+    view! {
+        <YaYaPopover
+            id
+            stack
+            elements=elements
+            close_cb=on_close
+            escape_cb=move |_| close_cb.call((id, None))
+            label=label
+        >
+            <nav
+                class="ya-ya-breadcrumbs"
+                aria-label="Вложенные пояснения"
+                hidden=move || depth.get() == 0
+            >
+                <For each=crumbs key=|crumb| crumb.clone() let:crumb>
+                    {
+                        let (at, word) = crumb;
+                        view! {
+                            <button
+                                class="ya-ya-crumb"
+                                aria-current=move || (depth.get() == at).then_some("page")
+                                disabled=move || depth.get() == at
+                                on:click=move |_| trail.update(|t| t.back(at))
+                            >
+                                {word}
+                            </button>
+                        }
+                    }
+                </For>
+            </nav>
+            <div class="ya-ya-lookup" on:click=on_lookup>
+                <div hidden=move || depth.get() > 0>
+                    <AnnotationCard
+                        annotation
+                        on_feedback
+                        on_retry=move |_| regenerate_cb.call(id)
+                        heading=word.clone()
+                        version
+                        on_version=move |shown| show_cb.call((id, shown))
+                        follow_up=request.clone()
+                        rejected
+                        auto_read
+                    />
+                </div>
+                {nested}
+            </div>
+        </YaYaPopover>
+    }
+}
+
+/// Compact one-line gloss of an annotated trigger, expands into [`YaTriggerPopover`] on click.
+#[component]
+pub fn YaGlossPopover(
+    trigger: PermanentTrigger,
+    stack: RwSignal<PopoverStack>,
+    #[prop(into)] annotation: Signal<Option<Result<Annotation, YaYaError>>>,
+    #[prop(into)] expand_cb: Callback<Uuid>,
+    #[prop(into)] dismiss_cb: Callback<Uuid>,
+) -> impl IntoView {
+    let id = trigger.id();
+    let elements = trigger.anchors();
+    let word = trigger.content();
+
+    let gloss = create_memo(move |_| {
+        annotation
+            .get()
+            .and_then(|res| res.ok())
+            .map(|a| a.gloss())
+            .filter(|g| !g.is_empty())
+    });
+
+    view! {
+        <YaYaPopover
+            id
+            stack
+            elements
+            close_cb=move |_| dismiss_cb.call(id)
+            label=format!("Кратко: {word}")
+            compact=true
+        >
+            <button
+                class="ya-ya-gloss"
+                title="Подробнее"
+                on:click=move |_| expand_cb.call(id)
+                on:mouseleave=move |_| dismiss_cb.call(id)
+            >
+                <strong>{word.clone()}</strong>
+                " — "
+                {move || gloss.get().unwrap_or_else(|| "…".to_string())}
+            </button>
+        </YaYaPopover>
+    }
+}
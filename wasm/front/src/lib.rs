@@ -1,7 +1,10 @@
 mod app;
 
+use app::Command;
 use cfg_if::cfg_if;
+use common::message::{listen, Message, PageSelection, Reply, Request, Sender};
 use leptos::*;
+use uuid::Uuid;
 use wasm_bindgen::prelude::*;
 use web_sys::HtmlElement;
 
@@ -18,7 +21,49 @@ pub fn main() {
     });
     console_error_panic_hook::set_once();
     log::info!("init log content");
-    mount_app().expect("mount app")
+    mount_app().expect("mount app");
+    if let Err(err) = listen(answer) {
+        log::error!("lib.rs :: Failed to listen to messages: {err:?}");
+    }
+}
+
+/// Answers the popup, the side panel and the background, requests for the background are not ours.
+fn answer(request: Request, _sender: Sender) -> Option<Reply> {
+    match request {
+        Request::GetSelection(message) => Some(message.reply(selection())),
+        Request::ScanDifficultWords(message) => {
+            app::run(Command::ScanDifficultWords);
+            Some(message.reply(()))
+        }
+        Request::OpenReader(message) => {
+            app::run(Command::OpenReader);
+            Some(message.reply(()))
+        }
+        Request::Explain(message) => {
+            app::run(Command::Explain(message.target));
+            Some(message.reply(()))
+        }
+        Request::GetExplanations(message) => Some(message.reply(app::explanations())),
+        Request::Reveal(message) => {
+            match Uuid::parse_str(&message.id) {
+                Ok(id) => app::run(Command::Reveal(id)),
+                Err(err) => log::error!("lib.rs :: Failed to reveal {}: {err:?}", message.id),
+            }
+            Some(message.reply(()))
+        }
+        Request::Annotate(_)
+        | Request::Ask(_)
+        | Request::RecordSuccess(_)
+        | Request::ExplanationsChanged(_) => None,
+    }
+}
+
+fn selection() -> Option<PageSelection> {
+    let win = web_sys::window()?;
+    let text = win.get_selection().ok()??.to_string().as_string()?;
+    let origin = win.location().origin().ok()?;
+
+    (!text.is_empty()).then_some(PageSelection { text, origin })
 }
 
 fn mount_app() -> Result<(), JsValue> {
@@ -41,9 +86,3 @@ fn mount_app() -> Result<(), JsValue> {
 
     Ok(())
 }
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_name = "sendMessage")]
-    pub async fn send_message() -> JsValue;
-}
@@ -1,10 +1,247 @@
+use common::{
+    settings::{load_settings, save_settings, Field, Invalid, Reopen, Settings},
+    theme::{Motion, Palette, Themed, FONT_SCALES},
+};
 use leptos::*;
 
 #[component]
 pub fn App() -> impl IntoView {
+    let saved = load_settings();
+    let draft = create_rw_signal(Settings::default());
+    // Number fields whose text is not a number, the draft keeps their last good value.
+    let unparsed = create_rw_signal(Vec::<Field>::new());
+
+    create_effect(move |_| {
+        draft.set(saved.get());
+        unparsed.set(vec![]);
+    });
+
+    let theme = create_memo(move |_| draft.get().theme);
+    let invalid = create_memo(move |_| {
+        let mut invalid = unparsed
+            .get()
+            .into_iter()
+            .map(Invalid::not_a_number)
+            .collect::<Vec<_>>();
+        if let Err(out_of_range) = draft.get().validate() {
+            let out_of_range = out_of_range
+                .into_iter()
+                .filter(|i| !invalid.iter().any(|u| u.field == i.field))
+                .collect::<Vec<_>>();
+            invalid.extend(out_of_range);
+        }
+        invalid
+    });
+    let changed = Signal::derive(move || draft.get() != saved.get() || !unparsed.get().is_empty());
+
+    let save_action = create_action(|settings: &Settings| {
+        let settings = *settings;
+        async move { save_settings(settings).await }
+    });
+    let status = move || match save_action.value().get() {
+        _ if save_action.pending().get() => "Сохраняем…",
+        _ if changed.get() => "Есть несохранённые изменения.",
+        Some(Err(_)) => "Не удалось сохранить, попробуйте ещё раз.",
+        Some(Ok(())) => "Сохранено.",
+        None => "",
+    };
+
+    let on_submit = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        if invalid.get_untracked().is_empty() {
+            save_action.dispatch(draft.get_untracked());
+        }
+    };
+
+    let on_reset = move |_| {
+        draft.set(Settings::default());
+        unparsed.set(vec![]);
+    };
+
+    let number_field = move |field: Field| {
+        let id = format!("ya-ya-field-{field:?}").to_lowercase();
+        let error_id = format!("{id}-error");
+        let error = move || {
+            invalid
+                .get()
+                .into_iter()
+                .find(|i| i.field == field)
+                .map(|i| i.message)
+        };
+
+        view! {
+            <div class="ya-ya-field">
+                <label for=id.clone()>{field.label()}</label>
+                <input
+                    id=id
+                    type="number"
+                    min="0"
+                    inputmode="numeric"
+                    prop:value=move || draft.get().get(field).to_string()
+                    aria-invalid=move || error().is_some().to_string()
+                    aria-describedby=error_id.clone()
+                    on:input=move |ev| {
+                        let parsed = event_target_value(&ev).trim().parse::<u64>();
+                        unparsed.update(|u| u.retain(|f| *f != field));
+                        match parsed {
+                            Ok(value) => draft.update(|d| d.set(field, value)),
+                            Err(_) => unparsed.update(|u| u.push(field)),
+                        }
+                    }
+                />
+                <p class="ya-ya-field-error" id=error_id role="alert">{error}</p>
+            </div>
+        }
+    };
+
     view! {
-        <p>
-            Ясно...
-        </p>
+        <Themed theme class="ya-ya-options">
+            <h1>Настройки</h1>
+            <form class="ya-ya-options-form" on:submit=on_submit novalidate>
+                <fieldset>
+                    <legend>Оформление</legend>
+
+                    <label class="ya-ya-field">
+                        <span>Цвета</span>
+                        <select on:change=move |ev| {
+                            if let Some(palette) = Palette::from_name(&event_target_value(&ev)) {
+                                draft.update(|d| d.theme.palette = palette);
+                            }
+                        }>
+                            {Palette::ALL.into_iter().map(|palette| view! {
+                                <option
+                                    value=palette.name()
+                                    selected=move || theme.get().palette == palette
+                                >
+                                    {palette.label()}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+
+                    <label class="ya-ya-field">
+                        <span>Размер текста</span>
+                        <select on:change=move |ev| {
+                            if let Ok(font_scale) = event_target_value(&ev).parse::<u16>() {
+                                draft.update(|d| d.theme.font_scale = font_scale);
+                            }
+                        }>
+                            {FONT_SCALES.into_iter().map(|scale| view! {
+                                <option
+                                    value=scale.to_string()
+                                    selected=move || theme.get().font_scale == scale
+                                >
+                                    {format!("{scale}%")}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+
+                    <label class="ya-ya-field ya-ya-field-check">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || theme.get().dyslexic_font
+                            on:change=move |ev| {
+                                let checked = event_target_checked(&ev);
+                                draft.update(|d| d.theme.dyslexic_font = checked);
+                            }
+                        />
+                        <span>Шрифт для дислексии</span>
+                    </label>
+
+                    <label class="ya-ya-field">
+                        <span>Анимации</span>
+                        <select on:change=move |ev| {
+                            if let Some(motion) = Motion::from_name(&event_target_value(&ev)) {
+                                draft.update(|d| d.theme.motion = motion);
+                            }
+                        }>
+                            {Motion::ALL.into_iter().map(|motion| view! {
+                                <option
+                                    value=motion.name()
+                                    selected=move || theme.get().motion == motion
+                                >
+                                    {motion.label()}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                </fieldset>
+
+                <fieldset>
+                    <legend>Пояснения</legend>
+
+                    {number_field(Field::FrequencyRank)}
+                    {number_field(Field::HoverDelay)}
+                    {number_field(Field::TriggerDelay)}
+
+                    <label class="ya-ya-field">
+                        <span>Повторно открывать пояснение</span>
+                        <select on:change=move |ev| {
+                            if let Some(reopen) = Reopen::from_name(&event_target_value(&ev)) {
+                                draft.update(|d| d.reopen = reopen);
+                            }
+                        }>
+                            {Reopen::ALL.into_iter().map(|reopen| view! {
+                                <option
+                                    value=reopen.name()
+                                    selected=move || draft.get().reopen == reopen
+                                >
+                                    {reopen.label()}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+
+                    {number_field(Field::MaxOpenPopovers)}
+                    {number_field(Field::DifficultWords)}
+
+                    <label class="ya-ya-field ya-ya-field-check">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || draft.get().auto_read
+                            on:change=move |ev| {
+                                let checked = event_target_checked(&ev);
+                                draft.update(|d| d.auto_read = checked);
+                            }
+                        />
+                        <span>Читать пояснения вслух</span>
+                    </label>
+                </fieldset>
+
+                <p class="ya-ya-options-status" role="status">{status}</p>
+
+                <div class="ya-ya-footer">
+                    <button class="ya-ya-button" type="button" on:click=on_reset>
+                        "Сбросить настройки"
+                    </button>
+                    <button
+                        class="ya-ya-button-cta"
+                        type="submit"
+                        disabled=move || !invalid.get().is_empty() || !changed.get() || save_action.pending().get()
+                    >
+                        "Сохранить"
+                    </button>
+                </div>
+            </form>
+
+            <section class="ya-ya-options-preview" aria-label="Пример пояснения">
+                <div class="ya-ya-popover ya-ya-popover-side-top">
+                    <div class="ya-ya-content">
+                        <pre class="ya-ya-pre">
+                            <h3>Синхротрон</h3>
+                            <p>
+                                "Синхротрон — это большой прибор в форме кольца. "
+                                <span class="ya-ya-reading">"В нём очень быстро разгоняют маленькие частицы."</span>
+                            </p>
+                        </pre>
+                        <div class="ya-ya-footer">
+                            <button class="ya-ya-button" type="button">"↺ Не понятно"</button>
+                            <button class="ya-ya-button-cta" type="button">"✔︎ Ясно"</button>
+                        </div>
+                    </div>
+                </div>
+            </section>
+        </Themed>
     }
 }
@@ -0,0 +1,144 @@
+use common::{
+    annotation::RequestKind,
+    message::{
+        active_tab_id, listen, send_to_tab, Explanation, GetExplanations, Message, Request, Reveal,
+    },
+    sanitize::render_annotation,
+    settings::{load_settings, provide_settings},
+    theme::{Themed, STYLE},
+};
+use leptos::*;
+
+use super::tabs;
+
+#[component]
+pub fn App() -> impl IntoView {
+    let settings = load_settings();
+    provide_settings(settings);
+    let theme = create_memo(move |_| settings.get().theme);
+
+    let (tab_changes, set_tab_changes) = create_signal(0_usize);
+    tabs::subscribe(move || set_tab_changes.update(|c| *c += 1));
+
+    let page = create_local_resource(
+        move || tab_changes.get(),
+        |_| async {
+            let tab_id = active_tab_id().await.ok()?;
+            let explanations = send_to_tab(tab_id, GetExplanations)
+                .await
+                .inspect_err(|err| log::warn!("app.rs :: No explanations from the page: {err:?}"))
+                .unwrap_or_default();
+
+            Some((tab_id, explanations))
+        },
+    );
+
+    let tab_id = create_rw_signal(Option::<i32>::None);
+    let explanations = create_rw_signal(Vec::<Explanation>::new());
+    create_effect(move |_| {
+        if let Some(page) = page.get() {
+            let (id, list) = page.unzip();
+            tab_id.set(id);
+            explanations.set(list.unwrap_or_default());
+        }
+    });
+
+    let listening = listen(move |request, sender| match request {
+        Request::ExplanationsChanged(message) => {
+            if sender.tab_id.is_some() && sender.tab_id == tab_id.get_untracked() {
+                _ = explanations.try_set(message.explanations.clone());
+            }
+            Some(message.reply(()))
+        }
+        _ => None,
+    });
+    if let Err(err) = listening {
+        log::error!("app.rs :: Failed to listen to the pages: {err:?}");
+    }
+
+    let reveal_action = create_action(move |id: &String| {
+        let id = id.clone();
+        async move {
+            let Some(tab_id) = tab_id.get_untracked() else {
+                return;
+            };
+            if let Err(err) = send_to_tab(tab_id, Reveal { id }).await {
+                log::error!("app.rs :: Failed to reveal the mark: {err:?}");
+            }
+        }
+    });
+
+    view! {
+        <style inner_html={STYLE}/>
+
+        <Themed theme class="ya-ya-panel">
+            <h1>Пояснения на странице</h1>
+            <Show
+                when=move || explanations.with(|e| !e.is_empty())
+                fallback=|| view! {
+                    <p>
+                        "Наведите курсор на слово или выделите текст, и пояснения появятся здесь."
+                    </p>
+                }
+            >
+                <ol class="ya-ya-panel-list">
+                    <For
+                        each=move || explanations.get()
+                        key=|e| (e.id.clone(), e.answer.clone(), e.rating, e.on_page)
+                        let:explanation
+                    >
+                        <ExplanationEntry
+                            explanation
+                            on_reveal=move |id| reveal_action.dispatch(id)
+                        />
+                    </For>
+                </ol>
+            </Show>
+        </Themed>
+    }
+}
+
+#[component]
+fn ExplanationEntry(
+    explanation: Explanation,
+    #[prop(into)] on_reveal: Callback<String>,
+) -> impl IntoView {
+    let Explanation {
+        id,
+        kind,
+        content,
+        context,
+        answer,
+        rating,
+        on_page,
+    } = explanation;
+
+    let rating = match rating {
+        Some(true) => "✔︎ Ясно",
+        Some(false) => "↺ Не понятно",
+        None => "Без оценки",
+    };
+
+    view! {
+        <li class="ya-ya-panel-entry">
+            <button
+                class="ya-ya-panel-reveal"
+                disabled=!on_page
+                title=if on_page { "Показать на странице" } else { "Метка снята со страницы" }
+                on:click=move |_| on_reveal.call(id.clone())
+            >
+                {content}
+            </button>
+            {(kind == RequestKind::Word).then(|| view! {
+                <p class="ya-ya-panel-context">{context}</p>
+            })}
+            {match answer {
+                Some(answer) => view! {
+                    <pre class="ya-ya-pre" inner_html=render_annotation(&answer)></pre>
+                }.into_view(),
+                None => view! { <p>"Пояснение ещё не готово."</p> }.into_view(),
+            }}
+            <p class="ya-ya-panel-rating">{rating}</p>
+        </li>
+    }
+}
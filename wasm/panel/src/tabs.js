@@ -0,0 +1,11 @@
+const rtm = typeof browser !== "undefined" ? browser : chrome;
+
+// Another tab became active, or the active one loaded another page.
+export function onTabChanged(callback) {
+  rtm.tabs.onActivated.addListener(() => callback());
+  rtm.tabs.onUpdated.addListener((_tabId, changeInfo, tab) => {
+    if (tab.active && changeInfo.status === "complete") {
+      callback();
+    }
+  });
+}
@@ -0,0 +1,15 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "/src/tabs.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "onTabChanged")]
+    fn on_tab_changed(callback: &JsValue);
+}
+
+/// Calls `on_change` when the page the panel is about changes.
+pub fn subscribe(on_change: impl Fn() + 'static) {
+    let callback = Closure::<dyn Fn()>::new(on_change);
+
+    // Listens for as long as the panel lives.
+    on_tab_changed(&callback.into_js_value());
+}
@@ -1,39 +1,67 @@
 use common::{
-    annotation::{annotate_text, success_record},
-    error::*,
-    loading::Loading,
+    annotation::{success_record, AnnotationRequest, RequestKind},
+    card::AnnotationCard,
+    message::{send_to_active_tab, GetSelection, OpenReader, ScanDifficultWords},
+    settings::{load_settings, provide_settings, use_settings},
+    theme::{Themed, ANIMATE_STYLE, STYLE},
 };
 use leptos::*;
 
-use super::send_message;
-
-const STYLE: &str = include_str!("../../../style.css");
-const ANIMATE_STYLE: &str = include_str!("../../../node_modules/animate.css/animate.min.css");
+use super::panel;
 
 #[component]
 pub fn App() -> impl IntoView {
+    let settings = load_settings();
+    provide_settings(settings);
+    let theme = create_memo(move |_| settings.get().theme);
+
     let selection_rsc = create_local_resource(
         || (),
         |_| async {
-            let msg = js_sys::Object::new();
-            js_sys::Reflect::set(&msg, &"action".into(), &"getSelectedText".into()).unwrap();
-
-            let val = send_message(msg.into()).await;
-
-            js_sys::Reflect::get(&val, &"selectedText".into())
+            send_to_active_tab(GetSelection)
+                .await
+                .inspect_err(|err| log::warn!("app.rs :: No selection from the page: {err:?}"))
                 .ok()
-                .zip(js_sys::Reflect::get(&val, &"origin".into()).ok())
-                .map(|(text, origin)| text.as_string().zip(origin.as_string()))
                 .flatten()
-                .filter(|(t, _)| !t.is_empty())
+                .map(|selection| (selection.text, selection.origin))
         },
     );
 
+    let scan_action = create_action(|_: &()| async {
+        if let Err(err) = send_to_active_tab(ScanDifficultWords).await {
+            log::error!("app.rs :: Failed to start difficult words scan: {err:?}");
+        }
+        window().close().unwrap();
+    });
+
+    let reader_action = create_action(|_: &()| async {
+        if let Err(err) = send_to_active_tab(OpenReader).await {
+            log::error!("app.rs :: Failed to open the reader view: {err:?}");
+        }
+        window().close().unwrap();
+    });
+
+    let open_panel = move |_| {
+        let opening = match panel::open() {
+            Ok(opening) => opening,
+            Err(err) => {
+                log::error!("app.rs :: Failed to open the side panel: {err:?}");
+                return;
+            }
+        };
+        spawn_local(async move {
+            match opening.await {
+                Ok(_) => window().close().unwrap(),
+                Err(err) => log::error!("app.rs :: Failed to open the side panel: {err:?}"),
+            }
+        });
+    };
+
     view! {
         <style inner_html={ANIMATE_STYLE}/>
         <style inner_html={STYLE}/>
 
-        <div class="ya-ya-action">
+        <Themed theme class="ya-ya-action">
             <h1>Пояснения</h1>
             {
                 move || match selection_rsc.get().flatten() {
@@ -44,10 +72,31 @@ pub fn App() -> impl IntoView {
                         <p>
                             "Чтобы получить пояснение выделите текст на странице и нажмите кнопку ЯЯ."
                         </p>
+                        <div class="ya-ya-footer">
+                            <button
+                                class="ya-ya-button-cta"
+                                on:click=move |_| scan_action.dispatch(())
+                                disabled=scan_action.pending()
+                            >
+                                "Найти сложные слова"
+                            </button>
+                        </div>
                     }.into_view()
                 }
             }
-        </div>
+            <div class="ya-ya-footer">
+                <button
+                    class="ya-ya-button"
+                    on:click=move |_| reader_action.dispatch(())
+                    disabled=reader_action.pending()
+                >
+                    "Упростить всю статью"
+                </button>
+                <button class="ya-ya-button" on:click=open_panel>
+                    "Все пояснения на странице"
+                </button>
+            </div>
+        </Themed>
     }
 }
 
@@ -56,11 +105,20 @@ pub fn AnnotateText(
     #[prop(into)] text: MaybeSignal<String>,
     #[prop(into)] origin: MaybeSignal<String>,
 ) -> impl IntoView {
-    let (previous_annotation, set_previous_annotation) = create_signal(Option::<String>::None);
+    let settings = use_settings();
+    let (rejected, set_rejected) = create_signal(Vec::<String>::new());
     let text = Signal::derive(move || text.get());
+    let origin = Signal::derive(move || origin.get());
     let annotation = create_local_resource(
-        move || (text.get(), origin.get(), previous_annotation.get()),
-        |(text, origin, previous)| async move { annotate_text(text, origin, previous).await },
+        move || (text.get(), origin.get(), rejected.get()),
+        |(text, origin, rejected)| {
+            let request = AnnotationRequest {
+                kind: RequestKind::Text,
+                content: text,
+                context: origin,
+            };
+            request.send(rejected)
+        },
     );
     let success_action = create_action(|(id, quality): &(usize, bool)| {
         let id = *id;
@@ -78,75 +136,38 @@ pub fn AnnotateText(
         success_action.dispatch((ann.id, quality));
 
         if !quality {
-            set_previous_annotation.set(Some(ann.annotation));
+            set_rejected.update(|rejected| rejected.push(ann.annotation));
         }
     });
 
-    let disabled_btns =
-        Signal::derive(move || success_action.pending().get() || annotation.loading().get());
-
-    let annotation_view = move || {
-        if let Some(annotation) = annotation.get() {
-            let text = annotation?.annotation;
-            let html_content = markdown::to_html(&text);
-            let html_content = html_content
-                .split(&['.', '?', '!', ':'])
-                .map(|s| s.trim())
-                .collect::<Vec<&str>>()
-                .join(".\n");
+    let follow_up = Signal::derive(move || AnnotationRequest {
+        kind: RequestKind::Text,
+        content: text.get(),
+        context: origin.get(),
+    });
 
-            Result::<View, YaYaError>::Ok(
-                view! {
-                    <div>
-                        <pre class="ya-ya-pre" inner_html=html_content/>
-                    </div>
-                    <div class="ya-ya-water-mark">
-                        <hr/>
-                        <p>Ответ создан языковой моделью и может содержать ошибки.</p>
-                        <hr/>
-                    </div>
-                    <div class="ya-ya-footer">
-                        <button
-                            class="ya-ya-button"
-                            on:click=move |_| {
-                                annotation_cb.call(false);
-                            }
-                            disabled=disabled_btns
-                        >
-                            "↺ Не понятно"
-                        </button>
-                        <button
-                            class="ya-ya-button-cta"
-                            on:click=move |_| {
-                                annotation_cb.call(true);
-                            }
-                            disabled=disabled_btns
-                        >
-                            "✔︎ Ясно"
-                        </button>
-                    </div>
-                }
-                .into_view(),
-            )
+    let current = Signal::derive(move || {
+        if annotation.loading().get() {
+            None
         } else {
-            Result::<View, YaYaError>::Ok(().into_view())
+            annotation.get()
         }
-    };
+    });
 
     view! {
         <div class="ya-ya-content">
             <blockquote class="ya-ya-text-original">
                 {text}
             </blockquote>
-            <Suspense fallback=Loading>
-                <ErrorBoundary fallback=move |errors| view!{
-                    <ErrorView errors=errors on_retry=move |_| {
-                        annotation.refetch();
-                    }/>
-                }>
-                    {annotation_view}
-                </ErrorBoundary>
-            </Suspense>
+            <AnnotationCard
+                annotation=current
+                on_feedback=annotation_cb
+                on_retry=move |_| annotation.refetch()
+                disabled=success_action.pending()
+                follow_up=follow_up
+                rejected=rejected
+                auto_read=Signal::derive(move || settings.get().auto_read)
+            />
         </div>
     }
 }
@@ -1,4 +1,5 @@
 mod app;
+mod panel;
 
 use leptos::*;
 use wasm_bindgen::prelude::*;
@@ -14,9 +15,3 @@ pub fn main() {
 fn mount_app() {
     mount_to_body(app::App);
 }
-
-#[wasm_bindgen(module = "/src/lib.js")]
-extern "C" {
-    #[wasm_bindgen(js_name = "sendMessage")]
-    pub async fn send_message(msg: JsValue) -> JsValue;
-}
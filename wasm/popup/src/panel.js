@@ -0,0 +1,9 @@
+const rtm = typeof browser !== "undefined" ? browser : chrome;
+
+// Opening the panel has to happen right in the click, without awaiting anything first.
+export function openPanel() {
+  if (rtm.sidebarAction) {
+    return rtm.sidebarAction.open();
+  }
+  return rtm.sidePanel.open({ windowId: rtm.windows.WINDOW_ID_CURRENT });
+}
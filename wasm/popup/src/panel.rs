@@ -0,0 +1,16 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen(module = "/src/panel.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = "openPanel", catch)]
+    fn open_panel() -> Result<js_sys::Promise, JsValue>;
+}
+
+/// Opens the side panel listing the explanations of the page.
+///
+/// Must be called from the click handler itself, the browsers refuse it otherwise,
+/// so the panel is requested right away and only its opening is awaited.
+pub fn open() -> Result<JsFuture, JsValue> {
+    Ok(JsFuture::from(open_panel()?))
+}